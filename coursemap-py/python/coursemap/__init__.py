@@ -65,7 +65,7 @@ class CourseMap:
 
         Args:
             filename (str): Output filename
-            format (str, optional): Output format ('svg', 'png', 'dot').
+            format (str, optional): Output format ('svg', 'png', 'dot', 'json', 'mermaid', 'html').
                                    Auto-detected from filename extension if not specified.
 
         Returns:
@@ -84,6 +84,12 @@ class CourseMap:
                 format = "svg"
             elif filename.endswith(".dot"):
                 format = "dot"
+            elif filename.endswith(".json"):
+                format = "json"
+            elif filename.endswith(".mmd"):
+                format = "mermaid"
+            elif filename.endswith(".html"):
+                format = "html"
             else:
                 format = "svg"  # default
 
@@ -92,23 +98,113 @@ class CourseMap:
 
         return self._cm.generate(self._input_dir, actual_filename, format)
 
+    def filter(self, phases=None, exclude_phases=None, metadata=None, paths=None,
+               exclude_paths=None):
+        """
+        Map of the courses that pass a filter
+
+        Courses between two kept courses are left out, but the kept courses
+        stay connected. Conditions add to the ``filter`` section of the
+        configuration.
+
+        Args:
+            phases (list, optional): Phases to draw (default: all)
+            exclude_phases (list, optional): Phases to leave out
+            metadata (list, optional): Conditions on the frontmatter that must
+                                       all hold, such as "week = 3",
+                                       "tags contains exam" or "level != advanced"
+            paths (list, optional): Path prefixes, relative to the input
+                                    directory, of the documents to draw
+            exclude_paths (list, optional): Path prefixes to leave out
+
+        Returns:
+            CourseMap: A new course map over the same documents
+
+        Raises:
+            ValueError: If a metadata condition cannot be read
+
+        Example:
+            >>> cm = coursemap.CourseMap("./courses")
+            >>> cm.filter(metadata=["tags contains exam"]).save("exam.svg")
+        """
+        filtered = CourseMap.__new__(CourseMap)
+        filtered._cm = self._cm.filter(phases, exclude_phases, metadata, paths, exclude_paths)
+        filtered._input_dir = self._input_dir
+        return filtered
+
+    def focus(self, id, upstream=False, downstream=False, depth=None):
+        """
+        Map of the courses around one lesson
+
+        Keeps the lesson, what it needs first (upstream) and what it unlocks
+        (downstream), for a small map on the lesson's own page.
+
+        Args:
+            id (str): Id of the lesson
+            upstream (bool): Show the courses the lesson depends on
+            downstream (bool): Show the courses that depend on the lesson;
+                               with neither or both, both sides are shown
+            depth (int, optional): How many steps away from the lesson to go
+                                   (default: unlimited)
+
+        Returns:
+            CourseMap: A new course map over the same documents
+
+        Example:
+            >>> cm = coursemap.CourseMap("./courses")
+            >>> cm.focus("micro", upstream=True, depth=1).show()
+        """
+        focused = CourseMap.__new__(CourseMap)
+        focused._cm = self._cm.focus(id, upstream, downstream, depth)
+        focused._input_dir = self._input_dir
+        return focused
+
     def get_config(self):
         """
         Get the current configuration as a dictionary
 
         Returns:
-            dict: Configuration dictionary with 'root_key', 'phase', and 'ignore' keys
+            dict: Configuration dictionary with 'root_key', 'phase', 'ignore' and
+                'include' keys
         """
         return self._cm.get_config()
 
-    def parse_documents(self):
+    def parse_documents(self, strict=None):
         """
         Parse documents and return metadata
 
+        Args:
+            strict (bool, optional): Stop at the first file that cannot be read
+                                     or parsed (default: from the configuration)
+
         Returns:
             list: List of document metadata dictionaries
+
+        Raises:
+            RuntimeError: If any file cannot be read or parsed; the message
+                          names every such file, or only the first with
+                          ``strict``
+        """
+        return self._cm.parse_documents(self._input_dir, strict)
+
+    def diagnostics(self):
+        """
+        Check the documents for problems without rendering the map
+
+        Reports missing prerequisites, cycles, duplicate ids, courses that
+        list themselves as prerequisites, phases missing from the configuration
+        and documents without course map metadata.
+
+        Returns:
+            list: List of diagnostic dictionaries with 'severity' ('warning' or
+                  'error'), 'kind', 'message', 'ids' and 'files' keys
+
+        Example:
+            >>> cm = coursemap.CourseMap("./courses")
+            >>> for d in cm.diagnostics():
+            ...     print(d["severity"], d["message"])
         """
-        return self._cm.parse_documents(self._input_dir)
+        return self._cm.diagnostics(self._input_dir)
 
 
 def show(input_dir=".", config=None):
@@ -30,13 +30,31 @@ class _CourseMap:
         """Generate SVG content as string"""
         return self._rust_cm.generate_inline_svg(input_dir)
 
+    def focus(self, id, upstream, downstream, depth):
+        """Copy that only draws the courses around one lesson"""
+        focused = _CourseMap.__new__(_CourseMap)
+        focused._rust_cm = self._rust_cm.focus(id, upstream, downstream, depth)
+        return focused
+
+    def filter(self, phases, exclude_phases, metadata, paths, exclude_paths):
+        """Copy that only draws the courses passing a filter"""
+        filtered = _CourseMap.__new__(_CourseMap)
+        filtered._rust_cm = self._rust_cm.filter(
+            phases, exclude_phases, metadata, paths, exclude_paths
+        )
+        return filtered
+
     def get_config(self):
         """Get configuration as dictionary"""
         return self._rust_cm.get_config()
 
-    def parse_documents(self, input_dir):
+    def parse_documents(self, input_dir, strict=None):
         """Parse documents and return metadata"""
-        return self._rust_cm.parse_documents(input_dir)
+        return self._rust_cm.parse_documents(input_dir, strict)
+
+    def diagnostics(self, input_dir):
+        """Collect diagnostics for the documents"""
+        return self._rust_cm.diagnostics(input_dir)
 
 
 def _generate_course_map(input_dir, output_path, format, config_path=None):
@@ -94,17 +94,18 @@ def _format_filename_with_extension(filename, format_type):
 
     Args:
         filename (str): Original filename
-        format_type (str): Format type ('svg', 'png', 'dot')
+        format_type (str): Format type ('svg', 'png', 'dot', 'json', 'mermaid', 'html')
 
     Returns:
         str: Filename with correct extension
     """
     # Remove any existing extension that doesn't match
     base_name = filename
-    for ext in [".svg", ".png", ".dot"]:
+    for ext in [".svg", ".png", ".dot", ".json", ".mmd", ".html"]:
         if base_name.endswith(ext):
             base_name = base_name[: -len(ext)]
             break
 
     # Add the correct extension
-    return f"{base_name}.{format_type}"
+    extension = "mmd" if format_type == "mermaid" else format_type
+    return f"{base_name}.{extension}"
@@ -2,9 +2,45 @@
 
 #![allow(clippy::useless_conversion)]
 
-use coursemap::{App, Config};
+use coursemap::config::{FocusConfig, FocusDirection};
+use coursemap::parser::ParseFailure;
+use coursemap::{App, Config, Diagnostic};
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
+use std::path::PathBuf;
+
+/// Convert diagnostics into a list of dictionaries
+fn diagnostics_to_py(py: Python<'_>, diagnostics: &[Diagnostic]) -> PyResult<Vec<PyObject>> {
+    let mut result = Vec::new();
+    for diagnostic in diagnostics {
+        let dict = PyDict::new_bound(py);
+        dict.set_item("severity", diagnostic.severity.to_string())?;
+        dict.set_item("kind", diagnostic.code())?;
+        dict.set_item("message", diagnostic.message())?;
+        dict.set_item("ids", diagnostic.ids())?;
+        let files: Vec<String> = diagnostic
+            .files
+            .iter()
+            .map(|f| f.to_string_lossy().to_string())
+            .collect();
+        dict.set_item("files", files)?;
+        result.push(dict.into());
+    }
+    Ok(result)
+}
+
+/// One line per file that could not be parsed, for an error message
+fn failures_message(failures: &[ParseFailure]) -> String {
+    let mut message = format!("Failed to parse {} document(s):", failures.len());
+    for failure in failures {
+        message.push_str(&format!(
+            "\n  {}: {}",
+            failure.file_path.display(),
+            failure.message
+        ));
+    }
+    message
+}
 
 #[pyclass]
 #[derive(Clone)]
@@ -24,9 +60,57 @@ impl CourseMap {
         Ok(CourseMap { config })
     }
 
+    /// A copy that only draws the courses around the lesson `id`
+    ///
+    /// Neither or both of `upstream` and `downstream` draw both sides.
+    #[pyo3(signature = (id, upstream = false, downstream = false, depth = None))]
+    pub fn focus(&self, id: &str, upstream: bool, downstream: bool, depth: Option<usize>) -> Self {
+        let mut config = self.config.clone();
+        config.focus = Some(FocusConfig::new(
+            id,
+            FocusDirection::from_sides(upstream, downstream),
+            depth,
+        ));
+        CourseMap { config }
+    }
+
+    /// A copy that only draws the courses passing a filter
+    ///
+    /// The conditions are added to the ones in the configuration.
+    #[pyo3(signature = (phases = None, exclude_phases = None, metadata = None, paths = None, exclude_paths = None))]
+    pub fn filter(
+        &self,
+        phases: Option<Vec<String>>,
+        exclude_phases: Option<Vec<String>>,
+        metadata: Option<Vec<String>>,
+        paths: Option<Vec<PathBuf>>,
+        exclude_paths: Option<Vec<PathBuf>>,
+    ) -> PyResult<Self> {
+        let mut config = self.config.clone();
+        let filter = &mut config.filter;
+        filter.phases.extend(phases.unwrap_or_default());
+        filter
+            .exclude_phases
+            .extend(exclude_phases.unwrap_or_default());
+        for condition in metadata.unwrap_or_default() {
+            let predicate = condition
+                .parse()
+                .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{e}")))?;
+            filter.metadata.push(predicate);
+        }
+        filter.paths.extend(paths.unwrap_or_default());
+        filter
+            .exclude_paths
+            .extend(exclude_paths.unwrap_or_default());
+        Ok(CourseMap { config })
+    }
+
     /// Ensure the output path has the correct extension for the given format
     fn ensure_correct_extension(&self, output_path: &str, format: &str) -> String {
-        let expected_ext = format!(".{format}");
+        let expected_ext = match format {
+            "mermaid" => ".mmd".to_string(),
+            _ => format!(".{format}"),
+        };
 
         // If the path already has the correct extension, return as-is
         if output_path.ends_with(&expected_ext) {
@@ -35,7 +119,7 @@ impl CourseMap {
 
         // Remove any existing extension that doesn't match
         let mut base_path = output_path.to_string();
-        for ext in &[".svg", ".png", ".dot"] {
+        for ext in &[".svg", ".png", ".dot", ".json", ".mmd", ".html"] {
             if base_path.ends_with(ext) {
                 base_path = base_path[..base_path.len() - ext.len()].to_string();
                 break;
@@ -128,18 +212,46 @@ impl CourseMap {
             }
             dict.set_item("phase", phases)?;
             dict.set_item("ignore", &self.config.ignore)?;
+            dict.set_item("include", &self.config.include)?;
 
             Ok(dict.into())
         })
     }
 
-    /// Parse documents in a directory and return metadata
+    /// Collect diagnostics (missing prerequisites, cycles, ...) without rendering
     #[pyo3(signature = (input_dir = "."))]
-    pub fn parse_documents(&self, input_dir: &str) -> PyResult<Vec<PyObject>> {
-        let documents =
-            coursemap::parser::parse_directory(input_dir, &self.config).map_err(|e| {
-                pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to parse documents: {e}"))
-            })?;
+    pub fn diagnostics(&self, input_dir: &str) -> PyResult<Vec<PyObject>> {
+        let app = App::new(self.config.clone());
+        let diagnostics = app.diagnostics(input_dir).map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to collect diagnostics: {e}"))
+        })?;
+
+        Python::with_gil(|py| diagnostics_to_py(py, &diagnostics))
+    }
+
+    /// Parse documents in a directory and return metadata
+    ///
+    /// Fails if any file cannot be read or parsed, naming every such file,
+    /// or only the first one with `strict` (default: from the configuration).
+    #[pyo3(signature = (input_dir = ".", strict = None))]
+    pub fn parse_documents(
+        &self,
+        input_dir: &str,
+        strict: Option<bool>,
+    ) -> PyResult<Vec<PyObject>> {
+        let mut config = self.config.clone();
+        if let Some(strict) = strict {
+            config.strict = strict;
+        }
+        let report = coursemap::parser::parse_directory(input_dir, &config).map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to parse documents: {e}"))
+        })?;
+        if !report.failures.is_empty() {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(failures_message(
+                &report.failures,
+            )));
+        }
+        let documents = report.documents;
 
         Python::with_gil(|py| {
             let mut result = Vec::new();
@@ -149,6 +261,8 @@ impl CourseMap {
                 dict.set_item("title", &doc.title)?;
                 dict.set_item("phase", &doc.phase)?;
                 dict.set_item("prerequisites", &doc.prerequisites)?;
+                dict.set_item("recommended", &doc.recommended)?;
+                dict.set_item("corequisites", &doc.corequisites)?;
                 dict.set_item("file_path", doc.file_path.to_string_lossy().as_ref())?;
                 result.push(dict.into());
             }
@@ -1,5 +1,6 @@
 """Tests for the coursemap Python package."""
 
+import json
 import pytest
 import tempfile
 import os
@@ -34,6 +35,8 @@ ignore:
             assert config['root_key'] == 'test-map'
             assert 'TestPhase' in config['phase']
             assert config['phase']['TestPhase']['face'] == 'red'
+            assert config['ignore'] == ['test.qmd']
+            assert config['include'] == []
         finally:
             os.unlink(config_path)
 
@@ -88,6 +91,8 @@ course-map:
   id: test-course
   phase: Pre
   prerequisites: ["intro"]
+  recommended: ["history"]
+  corequisites: ["lab"]
 ---
 # Test Course Content
 """)
@@ -101,6 +106,144 @@ course-map:
             assert doc['title'] == 'Test Course'
             assert doc['phase'] == 'Pre'
             assert doc['prerequisites'] == ['intro']
+            assert doc['recommended'] == ['history']
+            assert doc['corequisites'] == ['lab']
+
+    def test_diagnostics(self):
+        """Test that graph problems are reported as diagnostics."""
+        with tempfile.TemporaryDirectory() as temp_dir:
+            course_path = Path(temp_dir) / "advanced.qmd"
+            course_path.write_text("""---
+title: "Advanced"
+course-map:
+  id: advanced
+  phase: Post
+  prerequisites: ["missing"]
+---
+# Advanced
+""")
+
+            cm = coursemap.CourseMap(temp_dir)
+            diagnostics = cm.diagnostics()
+
+            assert len(diagnostics) == 1
+            diagnostic = diagnostics[0]
+            assert diagnostic['severity'] == 'warning'
+            assert diagnostic['kind'] == 'missing-prerequisite'
+            assert diagnostic['ids'] == ['advanced', 'missing']
+            assert diagnostic['files'][0].endswith('advanced.qmd')
+            assert 'missing' in diagnostic['message']
+
+    def test_diagnostics_parse_error(self):
+        """Test that files with broken frontmatter are reported, not dropped silently."""
+        with tempfile.TemporaryDirectory() as temp_dir:
+            (Path(temp_dir) / "broken.qmd").write_text("---\ntitle: [oops\n---\n")
+
+            cm = coursemap.CourseMap(temp_dir)
+            diagnostics = cm.diagnostics()
+
+            assert [d['kind'] for d in diagnostics] == ['parse-error']
+            assert diagnostics[0]['severity'] == 'error'
+            assert diagnostics[0]['files'][0].endswith('broken.qmd')
+            assert 'line' in diagnostics[0]['message']
+
+    def test_parse_documents_reports_failures(self):
+        """Test that parse_documents raises for broken files instead of dropping them."""
+        with tempfile.TemporaryDirectory() as temp_dir:
+            (Path(temp_dir) / "intro.qmd").write_text("""---
+title: "Introduction"
+course-map:
+  id: intro
+  phase: Pre
+---
+""")
+            (Path(temp_dir) / "broken.qmd").write_text("---\ntitle: [oops\n---\n")
+            (Path(temp_dir) / "other.qmd").write_text("---\ntitle: [oops\n---\n")
+
+            cm = coursemap.CourseMap(temp_dir)
+            with pytest.raises(RuntimeError) as error:
+                cm.parse_documents()
+            assert 'broken.qmd' in str(error.value)
+            assert 'other.qmd' in str(error.value)
+
+            with pytest.raises(RuntimeError) as error:
+                cm.parse_documents(strict=True)
+            assert 'broken.qmd' in str(error.value)
+            assert 'other.qmd' not in str(error.value)
+
+    def test_save_json(self):
+        """Test exporting the course map as JSON."""
+        with tempfile.TemporaryDirectory() as temp_dir:
+            course_path = Path(temp_dir) / "intro.qmd"
+            course_path.write_text("""---
+title: "Introduction"
+course-map:
+  id: intro
+  phase: Pre
+---
+# Introduction
+""")
+
+            cm = coursemap.CourseMap(temp_dir)
+            output_path = Path(temp_dir) / "course_map.json"
+            result = cm.save(str(output_path))
+
+            assert result == str(output_path)
+            graph = json.loads(output_path.read_text())
+            assert graph['version'] == 1
+            assert graph['nodes'][0]['id'] == 'intro'
+            assert graph['nodes'][0]['title'] == 'Introduction'
+            assert graph['edges'] == []
+
+    def test_focus(self):
+        """Test mapping the courses around one lesson."""
+        with tempfile.TemporaryDirectory() as temp_dir:
+            for id, prerequisites in [("intro", []), ("micro", ["intro"]), ("advanced", ["micro"])]:
+                (Path(temp_dir) / f"{id}.qmd").write_text(
+                    f"---\ncourse-map:\n  id: {id}\n  prerequisites: {prerequisites}\n---\n"
+                )
+
+            cm = coursemap.CourseMap(temp_dir)
+            output_path = Path(temp_dir) / "focus.json"
+            cm.focus("micro", upstream=True).save(str(output_path))
+
+            graph = json.loads(output_path.read_text())
+            assert [node['id'] for node in graph['nodes']] == ['intro', 'micro']
+            assert len(graph['edges']) == 1
+
+            # The original map is unchanged
+            cm.save(str(output_path))
+            assert len(json.loads(output_path.read_text())['nodes']) == 3
+
+            with pytest.raises(RuntimeError, match="nowhere"):
+                cm.focus("nowhere").save(str(output_path))
+
+    def test_filter(self):
+        """Test drawing only some of the courses."""
+        with tempfile.TemporaryDirectory() as temp_dir:
+            lessons = [("intro", "Pre", []), ("micro", "InClass", ["intro"]),
+                       ("advanced", "Post", ["micro"])]
+            for id, phase, prerequisites in lessons:
+                (Path(temp_dir) / f"{id}.qmd").write_text(
+                    f"---\nweek: {len(prerequisites) + 1}\ncourse-map:\n  id: {id}\n"
+                    f"  phase: {phase}\n  prerequisites: {prerequisites}\n---\n"
+                )
+
+            cm = coursemap.CourseMap(temp_dir)
+            output_path = Path(temp_dir) / "filter.json"
+            cm.filter(exclude_phases=["InClass"]).save(str(output_path))
+
+            graph = json.loads(output_path.read_text())
+            assert [node['id'] for node in graph['nodes']] == ['intro', 'advanced']
+            # intro still leads to advanced through the dropped micro
+            assert len(graph['edges']) == 1
+
+            cm.filter(metadata=["week = 1"]).save(str(output_path))
+            graph = json.loads(output_path.read_text())
+            assert [node['id'] for node in graph['nodes']] == ['intro']
+
+            with pytest.raises(ValueError):
+                cm.filter(metadata=["not a condition"])
 
     def test_show_method(self):
         """Test the show method (matplotlib-style)."""
@@ -29,6 +29,11 @@ setup_vendor_directory() {
         if [ -f "$COURSEMAP_RS_SRC/default-coursemap.yml" ]; then
             cp "$COURSEMAP_RS_SRC/default-coursemap.yml" "$VENDOR_SRC_DIR/"
         fi
+
+        # Copy the HTML output template if it exists
+        if [ -f "$COURSEMAP_RS_SRC/course-map.html" ]; then
+            cp "$COURSEMAP_RS_SRC/course-map.html" "$VENDOR_SRC_DIR/"
+        fi
         
         # Create simplified Cargo.toml for the vendored library
         cat > "$VENDOR_DIR/Cargo.toml" << 'EOF'
@@ -51,9 +56,11 @@ anyhow = "1.0"
 log = "0.4"
 serde = { version = "1.0", features = ["derive"] }
 serde_yaml = "0.9"
-gray_matter = "0.2"
+serde_json = "1.0"
 petgraph = "0.6"
 walkdir = "2.0"
+ignore = "0.4"
+rayon = "1.8"
 indexmap = { version = "2.0", features = ["serde"] }
 tempfile = "3.0"
 EOF
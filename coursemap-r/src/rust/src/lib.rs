@@ -1,15 +1,83 @@
 use extendr_api::prelude::*;
-use coursemap::App;
+use coursemap::config::{FocusConfig, FocusDirection};
+use coursemap::filter::FilterConfig;
+use coursemap::{App, Config};
 use std::fs;
+use std::path::PathBuf;
 use tempfile::NamedTempFile;
 
-/// Generate a course dependency map
-#[extendr]
-fn generate_course_map(input_dir: &str, output_path: &str, format: &str, config_path: Option<&str>) -> Result<String> {
-    let config = coursemap::load_config_from_path(config_path).map_err(|e| {
+/// Add the conditions of a filter list (see `filter_courses()`) to `filter`
+fn extend_filter(filter: &mut FilterConfig, list: List) -> Result<()> {
+    for (name, value) in list.iter() {
+        let values = value.as_string_vector().unwrap_or_default();
+        match name {
+            "phases" => filter.phases.extend(values),
+            "exclude_phases" => filter.exclude_phases.extend(values),
+            "metadata" => {
+                for condition in values {
+                    let predicate = condition
+                        .parse()
+                        .map_err(|e| Error::Other(format!("{}", e)))?;
+                    filter.metadata.push(predicate);
+                }
+            }
+            "paths" => filter.paths.extend(values.into_iter().map(PathBuf::from)),
+            "exclude_paths" => filter
+                .exclude_paths
+                .extend(values.into_iter().map(PathBuf::from)),
+            _ => return Err(Error::Other(format!("Unknown filter: {}", name))),
+        }
+    }
+    Ok(())
+}
+
+/// Load the configuration, narrowed by `filter` and focused on one lesson if
+/// `focus` is given
+fn load_config(
+    config_path: Option<&str>,
+    filter: Option<List>,
+    focus: Option<&str>,
+    upstream: bool,
+    downstream: bool,
+    depth: Option<i32>,
+) -> Result<Config> {
+    let mut config = coursemap::load_config_from_path(config_path).map_err(|e| {
         Error::Other(format!("Failed to load config: {}", e))
     })?;
 
+    if let Some(filter) = filter {
+        extend_filter(&mut config.filter, filter)?;
+    }
+    if let Some(id) = focus {
+        let depth = depth
+            .map(usize::try_from)
+            .transpose()
+            .map_err(|_| Error::Other("depth must not be negative".to_string()))?;
+        config.focus = Some(FocusConfig::new(
+            id,
+            FocusDirection::from_sides(upstream, downstream),
+            depth,
+        ));
+    }
+
+    Ok(config)
+}
+
+/// Generate a course dependency map
+#[extendr]
+fn generate_course_map(
+    input_dir: &str,
+    output_path: &str,
+    format: &str,
+    config_path: Option<&str>,
+    #[default = "NULL"] filter: Option<List>,
+    #[default = "NULL"] focus: Option<&str>,
+    #[default = "FALSE"] upstream: bool,
+    #[default = "FALSE"] downstream: bool,
+    #[default = "NULL"] depth: Option<i32>,
+) -> Result<String> {
+    let config = load_config(config_path, filter, focus, upstream, downstream, depth)?;
+
     let app = App::new(config);
     
     app.run(input_dir, output_path, format).map_err(|e| {
@@ -21,10 +89,16 @@ fn generate_course_map(input_dir: &str, output_path: &str, format: &str, config_
 
 /// Generate SVG content as string for inline embedding
 #[extendr]
-fn generate_inline_svg(input_dir: &str, config_path: Option<&str>) -> Result<String> {
-    let config = coursemap::load_config_from_path(config_path).map_err(|e| {
-        Error::Other(format!("Failed to load config: {}", e))
-    })?;
+fn generate_inline_svg(
+    input_dir: &str,
+    config_path: Option<&str>,
+    #[default = "NULL"] filter: Option<List>,
+    #[default = "NULL"] focus: Option<&str>,
+    #[default = "FALSE"] upstream: bool,
+    #[default = "FALSE"] downstream: bool,
+    #[default = "NULL"] depth: Option<i32>,
+) -> Result<String> {
+    let config = load_config(config_path, filter, focus, upstream, downstream, depth)?;
 
     // Create a temporary file that persists until we read it
     let temp_file = NamedTempFile::new().map_err(|e| {
@@ -54,10 +128,16 @@ fn generate_inline_svg(input_dir: &str, config_path: Option<&str>) -> Result<Str
 
 /// Generate DOT content as string (memory-efficient)
 #[extendr]
-fn generate_dot_string(input_dir: &str, config_path: Option<&str>) -> Result<String> {
-    let config = coursemap::load_config_from_path(config_path).map_err(|e| {
-        Error::Other(format!("Failed to load config: {}", e))
-    })?;
+fn generate_dot_string(
+    input_dir: &str,
+    config_path: Option<&str>,
+    #[default = "NULL"] filter: Option<List>,
+    #[default = "NULL"] focus: Option<&str>,
+    #[default = "FALSE"] upstream: bool,
+    #[default = "FALSE"] downstream: bool,
+    #[default = "NULL"] depth: Option<i32>,
+) -> Result<String> {
+    let config = load_config(config_path, filter, focus, upstream, downstream, depth)?;
 
     let app = App::new(config);
     app.generate_dot_string(input_dir).map_err(|e| {
@@ -80,31 +160,96 @@ fn graphviz_info() -> Result<String> {
 }
 
 /// Parse documents in a directory and return metadata
+///
+/// Fails if any file cannot be read or parsed, naming every such file, or
+/// only the first one with `strict` (default: from the configuration).
 #[extendr]
-fn parse_documents(input_dir: &str, config_path: Option<&str>) -> Result<List> {
-    let config = coursemap::load_config_from_path(config_path).map_err(|e| {
+fn parse_documents(
+    input_dir: &str,
+    config_path: Option<&str>,
+    #[default = "NULL"] strict: Option<bool>,
+) -> Result<List> {
+    let mut config = coursemap::load_config_from_path(config_path).map_err(|e| {
         Error::Other(format!("Failed to load config: {}", e))
     })?;
+    if let Some(strict) = strict {
+        config.strict = strict;
+    }
 
-    let documents = coursemap::parser::parse_directory(input_dir, &config).map_err(|e| {
+    let report = coursemap::parser::parse_directory(input_dir, &config).map_err(|e| {
         Error::Other(format!("Failed to parse documents: {}", e))
     })?;
+    if !report.failures.is_empty() {
+        let mut message = format!("Failed to parse {} document(s):", report.failures.len());
+        for failure in &report.failures {
+            message.push_str(&format!(
+                "\n  {}: {}",
+                failure.file_path.display(),
+                failure.message
+            ));
+        }
+        return Err(Error::Other(message));
+    }
+    let documents = report.documents;
 
     let mut result = List::new(documents.len());
     for (i, doc) in documents.iter().enumerate() {
-        let mut doc_list = List::new(5);
-        doc_list.set_names(&["id", "title", "phase", "prerequisites", "file_path"])?;
+        let mut doc_list = List::new(7);
+        doc_list.set_names(&[
+            "id",
+            "title",
+            "phase",
+            "prerequisites",
+            "recommended",
+            "corequisites",
+            "file_path",
+        ])?;
         doc_list.set_elt(0, doc.id.clone().into())?;
         doc_list.set_elt(1, doc.title.clone().into())?;
         doc_list.set_elt(2, doc.phase.clone().into())?;
         doc_list.set_elt(3, doc.prerequisites.clone().into())?;
-        doc_list.set_elt(4, doc.file_path.to_string_lossy().to_string().into())?;
+        doc_list.set_elt(4, doc.recommended.clone().into())?;
+        doc_list.set_elt(5, doc.corequisites.clone().into())?;
+        doc_list.set_elt(6, doc.file_path.to_string_lossy().to_string().into())?;
         result.set_elt(i, doc_list.into())?;
     }
 
     Ok(result)
 }
 
+/// Collect diagnostics for documents in a directory without rendering
+#[extendr]
+fn get_diagnostics(input_dir: &str, config_path: Option<&str>) -> Result<List> {
+    let config = coursemap::load_config_from_path(config_path).map_err(|e| {
+        Error::Other(format!("Failed to load config: {}", e))
+    })?;
+
+    let app = App::new(config);
+    let diagnostics = app.diagnostics(input_dir).map_err(|e| {
+        Error::Other(format!("Failed to collect diagnostics: {}", e))
+    })?;
+
+    let mut result = List::new(diagnostics.len());
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        let files: Vec<String> = diagnostic
+            .files
+            .iter()
+            .map(|f| f.to_string_lossy().to_string())
+            .collect();
+
+        let mut diagnostic_list = List::new(5);
+        diagnostic_list.set_names(&["severity", "kind", "message", "ids", "files"])?;
+        diagnostic_list.set_elt(0, diagnostic.severity.to_string().into())?;
+        diagnostic_list.set_elt(1, diagnostic.code().into())?;
+        diagnostic_list.set_elt(2, diagnostic.message().into())?;
+        diagnostic_list.set_elt(3, diagnostic.ids().into())?;
+        diagnostic_list.set_elt(4, files.into())?;
+        result.set_elt(i, diagnostic_list.into())?;
+    }
+
+    Ok(result)
+}
+
 /// Get configuration as list
 #[extendr]
 fn get_config(config_path: Option<&str>) -> Result<List> {
@@ -144,5 +289,6 @@ extendr_module! {
     fn graphviz_available;
     fn graphviz_info;
     fn parse_documents;
+    fn get_diagnostics;
     fn get_config;
 }
@@ -0,0 +1,90 @@
+//! Parsing a synthetic corpus of 10,000 course documents
+//!
+//! Run with `cargo bench --bench parse_directory`. Each pass parses the whole
+//! corpus on a rayon thread pool of the given size; one thread is the
+//! sequential baseline.
+
+use anyhow::Result;
+use coursemap::{parser, Config};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const COURSES: usize = 50;
+const LESSONS_PER_COURSE: usize = 200;
+const PASSES: usize = 5;
+
+fn write_corpus(dir: &Path) -> Result<()> {
+    for course in 0..COURSES {
+        let course_dir = dir.join(format!("course{course:02}"));
+        fs::create_dir_all(&course_dir)?;
+        for lesson in 0..LESSONS_PER_COURSE {
+            let prerequisites = if lesson == 0 {
+                String::new()
+            } else {
+                format!("c{course}-l{}", lesson - 1)
+            };
+            let body = "Lorem ipsum dolor sit amet, consectetur adipiscing elit.\n".repeat(40);
+            fs::write(
+                course_dir.join(format!("lesson{lesson:03}.qmd")),
+                format!(
+                    "---\ntitle: \"Course {course}, lesson {lesson}\"\nauthor: Someone\n\
+                     course-map:\n  id: c{course}-l{lesson}\n  phase: InClass\n  \
+                     prerequisites: [{prerequisites}]\n---\n\n# Lesson {lesson}\n\n{body}"
+                ),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Median time of a full parse with `threads` threads
+fn time_parse(dir: &str, config: &Config, threads: usize) -> Result<Duration> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()?;
+
+    let mut times = Vec::with_capacity(PASSES);
+    for _ in 0..PASSES {
+        let start = Instant::now();
+        let report = pool.install(|| parser::parse_directory(dir, config))?;
+        times.push(start.elapsed());
+        assert_eq!(report.documents.len(), COURSES * LESSONS_PER_COURSE);
+    }
+    times.sort();
+    Ok(times[PASSES / 2])
+}
+
+fn main() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    write_corpus(temp_dir.path())?;
+    let dir = temp_dir.path().to_str().unwrap();
+    let config = Config::default();
+
+    // Warm the file system cache
+    parser::parse_directory(dir, &config)?;
+
+    let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let baseline = time_parse(dir, &config, 1)?;
+    println!("parse_directory, {} files", COURSES * LESSONS_PER_COURSE);
+    println!("  1 thread: {baseline:>10.2?}");
+
+    let mut threads = 2;
+    while threads <= available {
+        let time = time_parse(dir, &config, threads)?;
+        println!(
+            "  {threads} threads: {time:>9.2?} ({:.1}x)",
+            baseline.as_secs_f64() / time.as_secs_f64()
+        );
+        threads *= 2;
+    }
+    if !available.is_power_of_two() {
+        let time = time_parse(dir, &config, available)?;
+        println!(
+            "  {available} threads: {time:>9.2?} ({:.1}x)",
+            baseline.as_secs_f64() / time.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
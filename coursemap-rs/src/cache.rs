@@ -0,0 +1,328 @@
+//! On-disk cache of parsed documents
+//!
+//! With `cache: true` in the configuration, [`crate::parser::parse_directory`]
+//! keeps every parsed [`Document`] in a `.coursemap-cache` file in the input
+//! directory, together with the file's modification time, size and content
+//! hash. On the next run a file whose time and size are unchanged is not read
+//! at all, and one whose content hash is unchanged (e.g. after a `git
+//! checkout`) is not parsed again.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::config::Config;
+use crate::extractors::Extractors;
+use crate::parser::Document;
+
+/// Name of the cache file, in the input directory
+pub const CACHE_FILE_NAME: &str = ".coursemap-cache";
+
+/// Bumped whenever the cache layout or the parser output changes
+const CACHE_VERSION: u32 = 2;
+
+/// A cached document and the state of the file it was parsed from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub modified: Option<SystemTime>,
+    pub size: u64,
+    /// FNV-1a hash of the file content
+    pub hash: u64,
+    /// Hash of the defaults inherited from `_quarto.yml` and `_metadata.yml`
+    pub defaults_hash: u64,
+    pub document: Document,
+}
+
+/// Parsed documents by path relative to the input directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParseCache {
+    version: u32,
+    /// Crate version, settings and extractors that change how documents are
+    /// parsed
+    fingerprint: String,
+    pub entries: HashMap<String, CacheEntry>,
+}
+
+impl ParseCache {
+    /// An empty cache for documents parsed with `config` and `extractors`
+    pub fn new(config: &Config, extractors: &Extractors) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            fingerprint: fingerprint(config, extractors),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Load the cache of `dir`
+    ///
+    /// A missing or unreadable cache, or one written by another version,
+    /// for another root key, extension list or set of extractors, is treated
+    /// as empty.
+    pub fn load(dir: &Path, config: &Config, extractors: &Extractors) -> Self {
+        let empty = Self::new(config, extractors);
+        let path = dir.join(CACHE_FILE_NAME);
+
+        let cache = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<ParseCache>(&content).ok());
+        match cache {
+            Some(cache)
+                if cache.version == empty.version && cache.fingerprint == empty.fingerprint =>
+            {
+                cache
+            }
+            Some(_) => {
+                log::debug!("Discarding outdated cache {}", path.display());
+                empty
+            }
+            None => empty,
+        }
+    }
+
+    /// Write the cache to `dir`, replacing the previous one
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(CACHE_FILE_NAME);
+
+        // Documents whose metadata cannot be represented in JSON (such as
+        // mappings with non-string keys) are left out and parsed every time
+        let entries: serde_json::Map<String, serde_json::Value> = self
+            .entries
+            .iter()
+            .filter_map(|(key, entry)| Some((key.clone(), serde_json::to_value(entry).ok()?)))
+            .collect();
+        let content = serde_json::to_string(&serde_json::json!({
+            "version": self.version,
+            "fingerprint": self.fingerprint,
+            "entries": entries,
+        }))?;
+
+        let mut file = tempfile::NamedTempFile::new_in(dir)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        file.write_all(content.as_bytes())?;
+        file.persist(&path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// The document parsed from `path`, from the cache if it is up to date
+    ///
+    /// `key` is the path relative to the input directory. Otherwise the file
+    /// is read and passed to `parse`. Returns the new cache entry, and whether
+    /// it was found unchanged in the cache.
+    pub fn get_or_parse(
+        &self,
+        path: &Path,
+        key: &str,
+        defaults_hash: u64,
+        parse: impl FnOnce(&str) -> Result<Document>,
+    ) -> Result<(CacheEntry, bool)> {
+        let metadata = fs::metadata(path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        let modified = metadata.modified().ok();
+        let size = metadata.len();
+        let cached = self
+            .entries
+            .get(key)
+            .filter(|entry| entry.defaults_hash == defaults_hash);
+
+        if let Some(entry) = cached {
+            if modified.is_some() && entry.modified == modified && entry.size == size {
+                return Ok((entry.clone(), true));
+            }
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        let hash = hash_bytes(content.as_bytes());
+
+        let document = match cached {
+            Some(entry) if entry.hash == hash => entry.document.clone(),
+            _ => parse(&content)?,
+        };
+
+        let entry = CacheEntry {
+            modified,
+            size,
+            hash,
+            defaults_hash,
+            document,
+        };
+        Ok((entry, false))
+    }
+}
+
+fn fingerprint(config: &Config, extractors: &Extractors) -> String {
+    let extensions: Vec<String> = config
+        .extensions
+        .iter()
+        .map(|extension| extension.to_lowercase())
+        .collect();
+    format!(
+        "{} {} [{}] {:016x}",
+        env!("CARGO_PKG_VERSION"),
+        config.root_key,
+        extensions.join(","),
+        hash_bytes(extractors.fingerprint().as_bytes())
+    )
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across releases
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::collections::HashMap as Map;
+    use std::path::PathBuf;
+
+    fn document(id: &str) -> Document {
+        Document::new(
+            id.to_string(),
+            String::new(),
+            PathBuf::from(format!("{id}.qmd")),
+            "Pre".to_string(),
+            vec![],
+            Map::new(),
+        )
+    }
+
+    #[test]
+    fn test_get_or_parse() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("intro.qmd");
+        fs::write(&path, "first")?;
+
+        let config = Config::default();
+        let mut cache = ParseCache::new(&config, &Extractors::default());
+        let parses = Cell::new(0);
+        let parse = |content: &str| {
+            parses.set(parses.get() + 1);
+            Ok(document(content))
+        };
+
+        let (entry, hit) = cache.get_or_parse(&path, "intro.qmd", 0, parse)?;
+        assert!(!hit);
+        assert_eq!(entry.document.id, "first");
+        cache.entries.insert("intro.qmd".to_string(), entry);
+
+        // Unchanged file
+        let (_, hit) = cache.get_or_parse(&path, "intro.qmd", 0, parse)?;
+        assert!(hit);
+        assert_eq!(parses.get(), 1);
+
+        // Different inherited defaults
+        let (_, hit) = cache.get_or_parse(&path, "intro.qmd", 1, parse)?;
+        assert!(!hit);
+        assert_eq!(parses.get(), 2);
+
+        // Same content with a new modification time is not parsed again
+        let entry = cache.entries.get_mut("intro.qmd").unwrap();
+        entry.modified = Some(SystemTime::UNIX_EPOCH);
+        let (entry, hit) = cache.get_or_parse(&path, "intro.qmd", 0, parse)?;
+        assert!(!hit);
+        assert_eq!(parses.get(), 2);
+        assert_ne!(entry.modified, Some(SystemTime::UNIX_EPOCH));
+
+        // Changed content
+        fs::write(&path, "second!")?;
+        let (entry, _) = cache.get_or_parse(&path, "intro.qmd", 0, parse)?;
+        assert_eq!(entry.document.id, "second!");
+        assert_eq!(parses.get(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_load() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let config = Config::default();
+
+        let mut cache = ParseCache::new(&config, &Extractors::default());
+        cache.entries.insert(
+            "intro.qmd".to_string(),
+            CacheEntry {
+                modified: Some(SystemTime::now()),
+                size: 5,
+                hash: hash_bytes(b"hello"),
+                defaults_hash: 0,
+                document: document("intro"),
+            },
+        );
+        cache.save(temp_dir.path())?;
+
+        let extractors = Extractors::default();
+        let loaded = ParseCache::load(temp_dir.path(), &config, &extractors);
+        assert_eq!(loaded.entries["intro.qmd"].document.id, "intro");
+        assert_eq!(loaded.entries["intro.qmd"].hash, hash_bytes(b"hello"));
+
+        // Another root key parses documents differently
+        let other = Config {
+            root_key: "other".to_string(),
+            ..Config::default()
+        };
+        assert!(ParseCache::load(temp_dir.path(), &other, &extractors)
+            .entries
+            .is_empty());
+
+        fs::write(temp_dir.path().join(CACHE_FILE_NAME), "not json")?;
+        assert!(ParseCache::load(temp_dir.path(), &config, &extractors)
+            .entries
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint_covers_extensions_and_extractors() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let config = Config::default();
+        let extractors = Extractors::default();
+
+        let mut cache = ParseCache::new(&config, &extractors);
+        cache.entries.insert(
+            "intro.md".to_string(),
+            CacheEntry {
+                modified: Some(SystemTime::now()),
+                size: 5,
+                hash: hash_bytes(b"hello"),
+                defaults_hash: 0,
+                document: document("intro"),
+            },
+        );
+        cache.save(temp_dir.path())?;
+        let reload = |config: &Config, extractors: &Extractors| {
+            ParseCache::load(temp_dir.path(), config, extractors).entries
+        };
+        assert_eq!(reload(&config, &Extractors::default()).len(), 1);
+
+        // Another extractor for an extension reads documents differently
+        let mut custom = Extractors::default();
+        custom.register("md", crate::extractors::RstFrontmatter);
+        assert!(reload(&config, &custom).is_empty());
+
+        let other = Config {
+            extensions: vec!["md".to_string()],
+            ..Config::default()
+        };
+        assert!(reload(&other, &extractors).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_bytes() {
+        assert_eq!(hash_bytes(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_bytes(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(hash_bytes(b"ab"), hash_bytes(b"ba"));
+    }
+}
@@ -5,6 +5,13 @@ use clap::{Parser, Subcommand, ValueEnum};
 #[cfg(feature = "cli")]
 use std::path::PathBuf;
 
+#[cfg(feature = "cli")]
+use crate::config::{Engine, FocusConfig, FocusDirection, InputMode};
+#[cfg(feature = "cli")]
+use crate::filter::MetadataPredicate;
+#[cfg(feature = "cli")]
+use crate::roots::CourseRoot;
+
 #[cfg(feature = "cli")]
 #[derive(Parser)]
 #[command(about = "Generate course dependency maps from Quarto/Markdown documents")]
@@ -15,7 +22,12 @@ pub struct Cli {
     pub command: Option<Commands>,
 
     /// Input directory containing course documents (when no subcommand is used)
-    pub input: Option<PathBuf>,
+    ///
+    /// Give several directories, or NAME=DIR, to map several courses at once;
+    /// their ids are namespaced by NAME (or the directory name), and lessons
+    /// can refer to other courses as NAME:ID.
+    #[arg(value_name = "INPUT")]
+    pub input: Vec<String>,
 
     /// Output file path
     #[arg(short, long, default_value = "course_map.svg")]
@@ -29,9 +41,79 @@ pub struct Cli {
     #[arg(short, long)]
     pub config: Option<PathBuf>,
 
+    /// Layout engine for SVG output (overrides the configuration file)
+    #[arg(short, long)]
+    pub engine: Option<Engine>,
+
+    /// Where the list of course documents comes from (overrides the configuration file)
+    #[arg(long)]
+    pub input_mode: Option<InputMode>,
+
     /// Verbose output
     #[arg(short, long)]
     pub verbose: bool,
+
+    /// Abort on the first file that cannot be read or parsed
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Cache parsed documents in .coursemap-cache and only re-parse changed files
+    #[arg(long)]
+    pub cache: bool,
+
+    /// Drop prerequisites implied by other prerequisites, and report them
+    #[arg(long)]
+    pub reduce: bool,
+
+    /// Only draw courses in this phase (repeatable)
+    #[arg(long, value_name = "PHASE")]
+    pub phase: Vec<String>,
+
+    /// Leave out courses in this phase (repeatable)
+    #[arg(long, value_name = "PHASE")]
+    pub exclude_phase: Vec<String>,
+
+    /// Only draw courses whose frontmatter matches, e.g. 'week = 3' or
+    /// 'tags contains exam' (repeatable)
+    #[arg(long, value_name = "CONDITION")]
+    pub metadata: Vec<MetadataPredicate>,
+
+    /// Only draw documents under this path, relative to the input directory
+    /// (repeatable)
+    #[arg(long, value_name = "PREFIX")]
+    pub path: Vec<PathBuf>,
+
+    /// Leave out documents under this path (repeatable)
+    #[arg(long, value_name = "PREFIX")]
+    pub exclude_path: Vec<PathBuf>,
+
+    /// Only draw the courses around the lesson with this id
+    #[arg(long, value_name = "ID")]
+    pub focus: Option<String>,
+
+    /// With --focus, draw the courses the lesson depends on
+    #[arg(long, requires = "focus")]
+    pub upstream: bool,
+
+    /// With --focus, draw the courses that depend on the lesson
+    #[arg(long, requires = "focus")]
+    pub downstream: bool,
+
+    /// With --focus, go at most this many steps away from the lesson
+    #[arg(long, value_name = "N", requires = "focus")]
+    pub depth: Option<usize>,
+
+    /// Keep running and regenerate the map when documents or the configuration change
+    #[arg(short, long)]
+    pub watch: bool,
+
+    /// How often to check for changes in watch mode, in milliseconds
+    #[arg(long, default_value_t = 500, value_name = "MS")]
+    pub poll_interval: u64,
+
+    /// How long edits must settle before regenerating in watch mode, in milliseconds
+    #[arg(long, default_value_t = 300, value_name = "MS")]
+    pub debounce: u64,
 }
 
 #[cfg(feature = "cli")]
@@ -44,6 +126,32 @@ pub enum Commands {
         #[arg(short, long)]
         config: Option<PathBuf>,
     },
+    /// Validate course documents without rendering a map
+    Check {
+        /// Input directories containing course documents (DIR or NAME=DIR)
+        #[arg(value_name = "INPUT", required = true)]
+        input: Vec<String>,
+
+        /// Configuration file path
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        /// Where the list of course documents comes from (overrides the configuration file)
+        #[arg(long)]
+        input_mode: Option<InputMode>,
+
+        /// Exit with a non-zero status on warnings as well as errors
+        #[arg(long)]
+        deny_warnings: bool,
+
+        /// Abort on the first file that cannot be read or parsed
+        #[arg(long)]
+        strict: bool,
+
+        /// Report prerequisites implied by other prerequisites
+        #[arg(long)]
+        reduce: bool,
+    },
 }
 
 #[cfg(feature = "cli")]
@@ -55,6 +163,12 @@ pub enum OutputFormat {
     Png,
     /// DOT format (Graphviz source)
     Dot,
+    /// JSON graph (nodes and edges)
+    Json,
+    /// Mermaid flowchart (for Markdown and Quarto)
+    Mermaid,
+    /// Standalone interactive HTML page
+    Html,
 }
 
 #[cfg(feature = "cli")]
@@ -64,6 +178,9 @@ impl std::fmt::Display for OutputFormat {
             OutputFormat::Svg => write!(f, "svg"),
             OutputFormat::Png => write!(f, "png"),
             OutputFormat::Dot => write!(f, "dot"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Mermaid => write!(f, "mermaid"),
+            OutputFormat::Html => write!(f, "html"),
         }
     }
 }
@@ -75,9 +192,25 @@ impl Cli {
         Self::parse()
     }
 
-    /// Get the input directory as a string
-    pub fn input_dir(&self) -> Option<&str> {
-        self.input.as_ref().and_then(|p| p.to_str())
+    /// Get the course roots given as input, if any
+    pub fn input_roots(&self) -> anyhow::Result<Option<Vec<CourseRoot>>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        CourseRoot::from_specs(&self.input).map(Some)
+    }
+
+    /// Get the focus given with --focus, --upstream, --downstream and --depth
+    ///
+    /// Neither or both of --upstream and --downstream draw both sides.
+    pub fn focus(&self) -> Option<FocusConfig> {
+        self.focus.as_ref().map(|id| {
+            FocusConfig::new(
+                id,
+                FocusDirection::from_sides(self.upstream, self.downstream),
+                self.depth,
+            )
+        })
     }
 
     /// Get the output path as a string
@@ -1,18 +1,271 @@
 //! Configuration management for the course map tool
 
 use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::fmt;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
+
+use crate::filter::FilterConfig;
+
+/// File names searched, in order, for a user configuration
+pub const CONFIG_FILE_NAMES: [&str; 3] = ["coursemap.yml", "coursemap.yaml", ".coursemap.yml"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     #[serde(rename = "root-key")]
     pub root_key: String,
     pub phase: IndexMap<String, PhaseConfig>,
+    /// Gitignore-style patterns, relative to the input directory, for files
+    /// to leave out
     pub ignore: Vec<String>,
+    /// Gitignore-style patterns for the files to read; empty for all files
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Extensions of course documents, without dots
+    #[serde(default = "default_extensions")]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub engine: Engine,
+    #[serde(default, rename = "duplicate-ids")]
+    pub duplicate_ids: DuplicateIds,
+    #[serde(default = "default_true", rename = "highlight-cycles")]
+    pub highlight_cycles: bool,
+    /// Link nodes in DOT, SVG and Mermaid output to their rendered pages
+    #[serde(default)]
+    pub links: Option<LinksConfig>,
+    /// Abort on the first file that cannot be read or parsed
+    #[serde(default)]
+    pub strict: bool,
+    /// Keep parsed documents in `.coursemap-cache` and only re-parse changed files
+    #[serde(default)]
+    pub cache: bool,
+    /// How to find the documents that make up the course
+    #[serde(default, rename = "input-mode")]
+    pub input_mode: InputMode,
+    /// Drop prerequisites implied by other prerequisites before rendering,
+    /// and report them as diagnostics
+    #[serde(default)]
+    pub reduce: bool,
+    /// Only draw the courses that pass this filter
+    #[serde(default)]
+    pub filter: FilterConfig,
+    /// Only draw the courses around one lesson
+    #[serde(default)]
+    pub focus: Option<FocusConfig>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_extensions() -> Vec<String> {
+    ["qmd", "md", "rmd", "ipynb"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+/// How course nodes link to their rendered pages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinksConfig {
+    /// Prefix for every link; empty for links relative to the map
+    #[serde(default, rename = "base-url")]
+    pub base_url: String,
+    /// Rendered extension for each source extension (without dots)
+    #[serde(default = "default_extension_map", rename = "extension-map")]
+    pub extension_map: IndexMap<String, String>,
+}
+
+fn default_extension_map() -> IndexMap<String, String> {
+    ["qmd", "md", "rmd", "ipynb"]
+        .into_iter()
+        .map(|ext| (ext.to_string(), "html".to_string()))
+        .collect()
+}
+
+impl Default for LinksConfig {
+    fn default() -> Self {
+        Self {
+            base_url: String::new(),
+            extension_map: default_extension_map(),
+        }
+    }
+}
+
+impl LinksConfig {
+    /// URL of the page rendered from `relative_path` (relative to the input root)
+    ///
+    /// The extension is mapped through `extension_map` (case-insensitively) and
+    /// left alone if it is not listed.
+    pub fn url_for(&self, relative_path: &Path) -> String {
+        let mut page = relative_path.to_path_buf();
+        if let Some(ext) = relative_path.extension().and_then(|e| e.to_str()) {
+            if let Some((_, rendered)) = self
+                .extension_map
+                .iter()
+                .find(|(source, _)| source.eq_ignore_ascii_case(ext))
+            {
+                page.set_extension(rendered);
+            }
+        }
+
+        let page = page
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if self.base_url.is_empty() {
+            page
+        } else {
+            format!("{}/{}", self.base_url.trim_end_matches('/'), page)
+        }
+    }
+}
+
+/// The part of the map drawn around one lesson
+///
+/// See [`crate::graph::CourseGraph::focus`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FocusConfig {
+    /// Id of the lesson, qualified in a map of several courses
+    pub id: String,
+    #[serde(default)]
+    pub direction: FocusDirection,
+    /// How many steps away from the lesson to go; unlimited if omitted
+    #[serde(default)]
+    pub depth: Option<usize>,
+}
+
+impl FocusConfig {
+    pub fn new(id: &str, direction: FocusDirection, depth: Option<usize>) -> Self {
+        Self {
+            id: id.to_string(),
+            direction,
+            depth,
+        }
+    }
+}
+
+/// Which side of a lesson a focused map shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusDirection {
+    /// The courses the lesson depends on
+    Upstream,
+    /// The courses that depend on the lesson
+    Downstream,
+    /// Both
+    #[default]
+    Both,
+}
+
+impl FocusDirection {
+    /// The direction that shows the given sides; both if neither is asked for
+    pub fn from_sides(upstream: bool, downstream: bool) -> Self {
+        match (upstream, downstream) {
+            (true, false) => FocusDirection::Upstream,
+            (false, true) => FocusDirection::Downstream,
+            _ => FocusDirection::Both,
+        }
+    }
+
+    pub fn upstream(self) -> bool {
+        self != FocusDirection::Downstream
+    }
+
+    pub fn downstream(self) -> bool {
+        self != FocusDirection::Upstream
+    }
+}
+
+impl fmt::Display for FocusDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FocusDirection::Upstream => write!(f, "upstream"),
+            FocusDirection::Downstream => write!(f, "downstream"),
+            FocusDirection::Both => write!(f, "both"),
+        }
+    }
+}
+
+/// The `ignore` and `include` patterns of a configuration, compiled for
+/// one input directory
+///
+/// Patterns follow `.gitignore` rules: a leading `/` anchors a pattern to the
+/// input directory, a trailing `/` matches directories, `**` spans
+/// directories, `?` and `[...]` match single characters and `!` re-includes a
+/// file excluded by an earlier pattern.
+#[derive(Debug, Clone)]
+pub struct FileMatcher {
+    root: std::path::PathBuf,
+    ignore: Gitignore,
+    include: Option<Gitignore>,
+}
+
+impl FileMatcher {
+    pub fn new(root: &Path, ignore: &[String], include: &[String]) -> Result<Self> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            Some(compile_patterns(root, include, "include")?)
+        };
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            ignore: compile_patterns(root, ignore, "ignore")?,
+            include,
+        })
+    }
+
+    /// Whether a directory is ignored, so that nothing below it is read
+    pub fn is_ignored_dir(&self, path: &Path) -> bool {
+        self.relative(path)
+            .is_some_and(|path| self.ignore.matched(path, true).is_ignore())
+    }
+
+    /// Whether a file is ignored or not included
+    ///
+    /// Paths outside the input directory are never excluded.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let Some(path) = self.relative(path) else {
+            return false;
+        };
+
+        if self
+            .ignore
+            .matched_path_or_any_parents(path, false)
+            .is_ignore()
+        {
+            return true;
+        }
+
+        self.include
+            .as_ref()
+            .is_some_and(|include| !include.matched_path_or_any_parents(path, false).is_ignore())
+    }
+
+    fn relative<'p>(&self, path: &'p Path) -> Option<&'p Path> {
+        let path = path.strip_prefix(&self.root).unwrap_or(path);
+        (!path.has_root() && path != Path::new("")).then_some(path)
+    }
+}
+
+fn compile_patterns(root: &Path, patterns: &[String], setting: &str) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .with_context(|| format!("Invalid {setting} pattern: {pattern}"))?;
+    }
+    builder
+        .build()
+        .with_context(|| format!("Invalid {setting} patterns"))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +273,100 @@ pub struct PhaseConfig {
     pub face: String,
 }
 
+/// Layout engine used to produce SVG output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// Use Graphviz when it is installed, otherwise the native renderer
+    #[default]
+    Auto,
+    /// Always use the Graphviz `dot` command
+    Graphviz,
+    /// Always use the built-in pure-Rust renderer
+    Native,
+}
+
+/// What to do when several documents declare the same course id
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateIds {
+    /// Report a warning; the last document read wins
+    #[default]
+    Warn,
+    /// Report an error and refuse to render the map
+    Error,
+    /// Report a warning and keep the first document read
+    KeepFirst,
+}
+
+/// Where the list of course documents comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum InputMode {
+    /// Every course document under the input directory
+    #[default]
+    Directory,
+    /// The documents listed in `_quarto.yml` (`website.sidebar` or
+    /// `book.chapters`), in their listed order
+    Quarto,
+}
+
+impl fmt::Display for InputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputMode::Directory => write!(f, "directory"),
+            InputMode::Quarto => write!(f, "quarto"),
+        }
+    }
+}
+
+impl FromStr for InputMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "directory" => Ok(InputMode::Directory),
+            "quarto" => Ok(InputMode::Quarto),
+            _ => Err(anyhow::anyhow!("Unknown input mode: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for DuplicateIds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuplicateIds::Warn => write!(f, "warn"),
+            DuplicateIds::Error => write!(f, "error"),
+            DuplicateIds::KeepFirst => write!(f, "keep-first"),
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Engine::Auto => write!(f, "auto"),
+            Engine::Graphviz => write!(f, "graphviz"),
+            Engine::Native => write!(f, "native"),
+        }
+    }
+}
+
+impl FromStr for Engine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Engine::Auto),
+            "graphviz" => Ok(Engine::Graphviz),
+            "native" => Ok(Engine::Native),
+            _ => Err(anyhow::anyhow!("Unknown engine: {}", s)),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         let default_config = include_str!("default-coursemap.yml");
@@ -52,9 +399,7 @@ impl Config {
         let dir = dir.as_ref();
 
         // Try to load from user config file locations
-        let config_paths = ["coursemap.yml", "coursemap.yaml", ".coursemap.yml"];
-
-        for path in &config_paths {
+        for path in &CONFIG_FILE_NAMES {
             let full_path = dir.join(path);
             if full_path.exists() {
                 return Self::from_file(full_path);
@@ -80,38 +425,20 @@ impl Config {
             })
     }
 
-    /// Check if a file should be ignored
-    pub fn should_ignore(&self, file_path: &str) -> bool {
-        self.ignore.iter().any(|pattern| {
-            if let Some(stripped) = pattern.strip_prefix('/') {
-                // Absolute path pattern - check if file path ends with the pattern (without leading /)
-                file_path.ends_with(stripped)
-            } else if pattern.contains('*') {
-                // Wildcard pattern - simple glob matching
-                self.matches_glob_pattern(file_path, pattern)
-            } else {
-                // Simple substring matching
-                file_path.contains(pattern)
-            }
-        })
+    /// Matcher for the `ignore` and `include` patterns below `root`
+    pub fn file_matcher(&self, root: &Path) -> Result<FileMatcher> {
+        FileMatcher::new(root, &self.ignore, &self.include)
     }
 
-    /// Simple glob pattern matching for basic wildcards
-    fn matches_glob_pattern(&self, file_path: &str, pattern: &str) -> bool {
-        if pattern == "*" {
-            return true;
-        }
-
-        if let Some(extension) = pattern.strip_prefix("*.") {
-            // Extension matching like "*.tmp"
-            file_path.ends_with(&format!(".{extension}"))
-        } else if let Some(prefix) = pattern.strip_suffix("*") {
-            // Prefix matching like "temp*"
-            file_path.contains(prefix)
-        } else {
-            // For more complex patterns, fall back to simple contains
-            file_path.contains(pattern)
-        }
+    /// Check if a file should be ignored
+    ///
+    /// `file_path` is relative to the input directory. Files that match an
+    /// `ignore` pattern, or that no `include` pattern matches, are ignored.
+    /// Invalid patterns match nothing here; [`Config::file_matcher`] reports
+    /// them.
+    pub fn should_ignore(&self, file_path: &str) -> bool {
+        self.file_matcher(Path::new(""))
+            .is_ok_and(|matcher| matcher.is_excluded(Path::new(file_path)))
     }
 
     /// Get all available phases
@@ -282,6 +609,109 @@ ignore: []
     }
 
     #[test]
+    fn test_engine_setting() -> Result<()> {
+        assert_eq!(Config::default().engine, Engine::Auto);
+
+        let config: Config =
+            serde_yaml::from_str("root-key: course-map\nphase: {}\nignore: []\nengine: native\n")?;
+        assert_eq!(config.engine, Engine::Native);
+        assert_eq!("Graphviz".parse::<Engine>()?, Engine::Graphviz);
+        assert!("cairo".parse::<Engine>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_ids_setting() -> Result<()> {
+        assert_eq!(Config::default().duplicate_ids, DuplicateIds::Warn);
+
+        let config: Config = serde_yaml::from_str(
+            "root-key: course-map\nphase: {}\nignore: []\nduplicate-ids: keep-first\n",
+        )?;
+        assert_eq!(config.duplicate_ids, DuplicateIds::KeepFirst);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_input_mode_setting() -> Result<()> {
+        assert_eq!(Config::default().input_mode, InputMode::Directory);
+
+        let config: Config = serde_yaml::from_str(
+            "root-key: course-map\nphase: {}\nignore: []\ninput-mode: quarto\n",
+        )?;
+        assert_eq!(config.input_mode, InputMode::Quarto);
+        assert_eq!("Quarto".parse::<InputMode>()?, InputMode::Quarto);
+        assert!("sidebar".parse::<InputMode>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_focus_setting() -> Result<()> {
+        assert_eq!(Config::default().focus, None);
+
+        let config: Config = serde_yaml::from_str(
+            "root-key: course-map\nphase: {}\nignore: []\nfocus:\n  id: micro\n  depth: 2\n",
+        )?;
+        assert_eq!(
+            config.focus,
+            Some(FocusConfig::new("micro", FocusDirection::Both, Some(2)))
+        );
+
+        assert_eq!(
+            FocusDirection::from_sides(true, false),
+            FocusDirection::Upstream
+        );
+        assert_eq!(
+            FocusDirection::from_sides(false, false),
+            FocusDirection::Both
+        );
+        assert!(!FocusDirection::Downstream.upstream());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_setting() -> Result<()> {
+        assert!(Config::default().filter.is_empty());
+
+        let config: Config = serde_yaml::from_str(
+            "root-key: course-map\nphase: {}\nignore: []\nfilter:\n  phases: [Pre]\n  metadata: ['week = 3']\n",
+        )?;
+        assert_eq!(config.filter.phases, vec!["Pre"]);
+        assert_eq!(config.filter.metadata[0].key(), "week");
+        assert!(config.filter.paths.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_links_setting() -> Result<()> {
+        assert_eq!(Config::default().links, None);
+
+        let config: Config = serde_yaml::from_str(
+            "root-key: course-map\nphase: {}\nignore: []\nlinks:\n  base-url: https://example.org/course/\n",
+        )?;
+        let links = config.links.expect("links should be configured");
+        assert_eq!(links.extension_map["qmd"], "html");
+        assert_eq!(
+            links.url_for(Path::new("week1/lab.Rmd")),
+            "https://example.org/course/week1/lab.html"
+        );
+
+        let links = LinksConfig {
+            extension_map: IndexMap::from([("qmd".to_string(), "htm".to_string())]),
+            ..LinksConfig::default()
+        };
+        assert_eq!(links.url_for(Path::new("intro.qmd")), "intro.htm");
+        assert_eq!(links.url_for(Path::new("notes.txt")), "notes.txt");
+
+        Ok(())
+    }
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn test_ignore_patterns() {
         let mut config = Config::default();
         config.ignore = vec![
@@ -290,9 +720,63 @@ ignore: []
             "*.tmp".to_string(),
         ];
 
-        assert!(config.should_ignore("some/path/index.qmd"));
+        assert!(config.should_ignore("index.qmd"));
+        assert!(!config.should_ignore("some/path/index.qmd"));
         assert!(config.should_ignore("README.md"));
+        assert!(config.should_ignore("docs/README.md"));
+        assert!(!config.should_ignore("notREADME.md"));
         assert!(config.should_ignore("file.tmp"));
         assert!(!config.should_ignore("intro.qmd"));
     }
+
+    #[test]
+    fn test_glob_patterns() {
+        let config = Config {
+            ignore: vec![
+                "drafts/**/*.qmd".to_string(),
+                "!drafts/**/keep.qmd".to_string(),
+                "week?-old.qmd".to_string(),
+                "lab[0-9].md".to_string(),
+                "_site/".to_string(),
+            ],
+            ..Config::default()
+        };
+
+        assert!(config.should_ignore("drafts/a.qmd"));
+        assert!(config.should_ignore("drafts/2024/spring/b.qmd"));
+        assert!(!config.should_ignore("drafts/2024/keep.qmd"));
+        assert!(!config.should_ignore("lessons/drafts.qmd"));
+        assert!(config.should_ignore("week1-old.qmd"));
+        assert!(!config.should_ignore("week10-old.qmd"));
+        assert!(config.should_ignore("lab3.md"));
+        assert!(!config.should_ignore("labA.md"));
+        assert!(config.should_ignore("_site/intro.md"));
+
+        let matcher = config.file_matcher(Path::new("/course")).unwrap();
+        assert!(matcher.is_excluded(Path::new("/course/drafts/a.qmd")));
+        assert!(matcher.is_ignored_dir(Path::new("/course/_site")));
+        assert!(!matcher.is_excluded(Path::new("/elsewhere/drafts/a.qmd")));
+
+        let invalid = Config {
+            ignore: vec!["lab{1,2.md".to_string()],
+            ..Config::default()
+        };
+        let error = invalid.file_matcher(Path::new("")).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid ignore pattern: lab{1,2.md");
+    }
+
+    #[test]
+    fn test_include_patterns() {
+        let config = Config {
+            ignore: vec!["lessons/draft.qmd".to_string()],
+            include: vec!["lessons/".to_string(), "*.ipynb".to_string()],
+            ..Config::default()
+        };
+
+        assert!(!config.should_ignore("lessons/intro.qmd"));
+        assert!(!config.should_ignore("lessons/week1/lab.qmd"));
+        assert!(!config.should_ignore("notebooks/data.ipynb"));
+        assert!(config.should_ignore("lessons/draft.qmd"));
+        assert!(config.should_ignore("about.qmd"));
+    }
 }
@@ -0,0 +1,284 @@
+//! Diagnostics reported while building a course map
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::graph::EdgeKind;
+use crate::parser::Document;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// What a diagnostic is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// A course lists a prerequisite (or a recommended course or
+    /// corequisite, as given by `edge`) that no document defines
+    MissingPrerequisite {
+        course: String,
+        prerequisite: String,
+        edge: EdgeKind,
+    },
+    /// Courses that directly or indirectly require each other
+    ///
+    /// `path` is one explicit cycle (`a -> b -> c -> a` is `[a, b, c]`);
+    /// `members` is the whole group of courses tied up in cycles with it.
+    Cycle {
+        path: Vec<String>,
+        members: Vec<String>,
+    },
+    /// Several documents declare the same course id
+    DuplicateId { id: String },
+    /// A course lists itself as a prerequisite
+    SelfLoop { id: String },
+    /// A course lists a prerequisite (or recommended course, as given by
+    /// `edge`) that it already depends on through `via`
+    RedundantPrerequisite {
+        course: String,
+        prerequisite: String,
+        via: String,
+        edge: EdgeKind,
+    },
+    /// A course uses a phase that is not defined in the configuration
+    UnknownPhase { course: String, phase: String },
+    /// A document has no course map metadata block
+    MissingCourseMap { id: String },
+    /// A file could not be read or its frontmatter could not be parsed
+    ParseError { message: String },
+}
+
+/// A single problem found in the course documents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    /// Source files involved, in document order
+    pub files: Vec<PathBuf>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, kind: DiagnosticKind, files: Vec<PathBuf>) -> Self {
+        Self {
+            severity,
+            kind,
+            files,
+        }
+    }
+
+    pub fn warning(kind: DiagnosticKind, files: Vec<PathBuf>) -> Self {
+        Self::new(Severity::Warning, kind, files)
+    }
+
+    pub fn error(kind: DiagnosticKind, files: Vec<PathBuf>) -> Self {
+        Self::new(Severity::Error, kind, files)
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Stable, machine-readable name of the diagnostic kind
+    pub fn code(&self) -> &'static str {
+        match self.kind {
+            DiagnosticKind::MissingPrerequisite { .. } => "missing-prerequisite",
+            DiagnosticKind::Cycle { .. } => "cycle",
+            DiagnosticKind::DuplicateId { .. } => "duplicate-id",
+            DiagnosticKind::SelfLoop { .. } => "self-loop",
+            DiagnosticKind::RedundantPrerequisite { .. } => "redundant-prerequisite",
+            DiagnosticKind::UnknownPhase { .. } => "unknown-phase",
+            DiagnosticKind::MissingCourseMap { .. } => "missing-course-map",
+            DiagnosticKind::ParseError { .. } => "parse-error",
+        }
+    }
+
+    /// Course ids the diagnostic refers to
+    pub fn ids(&self) -> Vec<String> {
+        match &self.kind {
+            DiagnosticKind::MissingPrerequisite {
+                course,
+                prerequisite,
+                ..
+            } => vec![course.clone(), prerequisite.clone()],
+            DiagnosticKind::Cycle { members, .. } => members.clone(),
+            DiagnosticKind::RedundantPrerequisite {
+                course,
+                prerequisite,
+                via,
+                ..
+            } => vec![course.clone(), prerequisite.clone(), via.clone()],
+            DiagnosticKind::UnknownPhase { course, .. } => vec![course.clone()],
+            DiagnosticKind::DuplicateId { id }
+            | DiagnosticKind::SelfLoop { id }
+            | DiagnosticKind::MissingCourseMap { id } => vec![id.clone()],
+            DiagnosticKind::ParseError { .. } => Vec::new(),
+        }
+    }
+
+    /// Human-readable description without the severity prefix
+    pub fn message(&self) -> String {
+        match &self.kind {
+            DiagnosticKind::MissingPrerequisite {
+                course,
+                prerequisite,
+                edge,
+            } => {
+                let what = match edge {
+                    EdgeKind::Required => "Prerequisite",
+                    EdgeKind::Recommended => "Recommended course",
+                    EdgeKind::Corequisite => "Corequisite",
+                };
+                format!("{what} '{prerequisite}' for course '{course}' not found in documents")
+            }
+            DiagnosticKind::Cycle { path, members } => {
+                let cycle: Vec<&str> = path
+                    .iter()
+                    .chain(path.first())
+                    .map(String::as_str)
+                    .collect();
+                let mut message = format!(
+                    "The course dependency graph contains a cycle: {}",
+                    cycle.join(" -> ")
+                );
+                if members.len() > path.len() {
+                    message.push_str(&format!(" (involving {})", members.join(", ")));
+                }
+                message
+            }
+            DiagnosticKind::DuplicateId { id } => {
+                format!("Course id '{id}' is declared by more than one document")
+            }
+            DiagnosticKind::SelfLoop { id } => {
+                format!("Course '{id}' lists itself as a prerequisite")
+            }
+            DiagnosticKind::RedundantPrerequisite {
+                course,
+                prerequisite,
+                via,
+                edge,
+            } => {
+                let what = match edge {
+                    EdgeKind::Recommended => "a recommended course",
+                    EdgeKind::Required | EdgeKind::Corequisite => "a prerequisite",
+                };
+                format!(
+                    "Course '{course}' lists '{prerequisite}' as {what}, which is already implied by '{via}'"
+                )
+            }
+            DiagnosticKind::UnknownPhase { course, phase } => {
+                format!("Course '{course}' uses phase '{phase}', which is not defined in the configuration")
+            }
+            DiagnosticKind::MissingCourseMap { id } => {
+                format!("Document '{id}' has no course map metadata")
+            }
+            DiagnosticKind::ParseError { message } => {
+                format!("Skipped a document that could not be parsed: {message}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message())
+    }
+}
+
+/// Check parsed documents against the configuration
+///
+/// Reports documents without a course map block (under `config.root_key`)
+/// and courses whose phase is not listed in `config.phase`.
+pub fn check_documents(documents: &[Document], config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for doc in documents {
+        if !doc.metadata.contains_key(&config.root_key) {
+            diagnostics.push(Diagnostic::warning(
+                DiagnosticKind::MissingCourseMap { id: doc.id.clone() },
+                vec![doc.file_path.clone()],
+            ));
+        } else if !config.phase.contains_key(&doc.phase) {
+            diagnostics.push(Diagnostic::warning(
+                DiagnosticKind::UnknownPhase {
+                    course: doc.id.clone(),
+                    phase: doc.phase.clone(),
+                },
+                vec![doc.file_path.clone()],
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn document(id: &str, phase: &str, with_course_map: bool) -> Document {
+        let mut metadata = HashMap::new();
+        if with_course_map {
+            metadata.insert("course-map".to_string(), serde_yaml::Value::Null);
+        }
+        Document::new(
+            id.to_string(),
+            String::new(),
+            PathBuf::from(format!("{id}.qmd")),
+            phase.to_string(),
+            vec![],
+            metadata,
+        )
+    }
+
+    #[test]
+    fn test_check_documents() {
+        let config = Config::default();
+        let documents = vec![
+            document("intro", "Pre", true),
+            document("lab", "Lab", true),
+            document("README", "Unknown", false),
+        ];
+
+        let diagnostics = check_documents(&documents, &config);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].kind,
+            DiagnosticKind::UnknownPhase {
+                course: "lab".to_string(),
+                phase: "Lab".to_string(),
+            }
+        );
+        assert_eq!(diagnostics[1].code(), "missing-course-map");
+        assert_eq!(diagnostics[1].files, vec![PathBuf::from("README.qmd")]);
+        assert!(diagnostics.iter().all(|d| !d.is_error()));
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = Diagnostic::error(
+            DiagnosticKind::SelfLoop {
+                id: "loop".to_string(),
+            },
+            vec![],
+        );
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: Course 'loop' lists itself as a prerequisite"
+        );
+    }
+}
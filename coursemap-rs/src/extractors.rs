@@ -0,0 +1,314 @@
+//! Frontmatter extractors for the supported document formats
+//!
+//! Each file extension maps to a [`FrontmatterExtractor`] that finds the
+//! metadata block of a document. The built-in extractors cover Markdown-style
+//! YAML frontmatter (Quarto, R Markdown, Markdown and AsciiDoc), Jupyter
+//! notebooks and reStructuredText; others can be registered on an
+//! [`Extractors`] registry.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::frontmatter;
+
+/// Reads the metadata of one document format
+pub trait FrontmatterExtractor: Send + Sync {
+    /// The metadata mapping of a document, or `None` if it has none
+    ///
+    /// `title` and the configured root key are read from the mapping; the
+    /// other keys are kept as document metadata.
+    fn extract(&self, content: &str, config: &Config) -> Result<Option<serde_yaml::Mapping>>;
+
+    /// Identifies the extractor in the parse cache, so that documents are
+    /// parsed again when another extractor handles their extension
+    ///
+    /// Defaults to the type name; override it if instances of one type read
+    /// documents differently.
+    fn name(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+}
+
+/// Pandoc-style YAML frontmatter between `---` lines at the top of the file
+///
+/// Used for Quarto, R Markdown and Markdown, and for AsciiDoc files with
+/// front matter (as read by Asciidoctor's `skip-front-matter`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YamlFrontmatter;
+
+impl FrontmatterExtractor for YamlFrontmatter {
+    fn extract(&self, content: &str, _config: &Config) -> Result<Option<serde_yaml::Mapping>> {
+        Ok(frontmatter::parse(content)?)
+    }
+}
+
+/// Frontmatter of a Jupyter notebook
+///
+/// Quarto reads the YAML from the first raw cell that holds a frontmatter
+/// block. `title` and the course map block may also be given in the
+/// notebook-level metadata; the raw cell takes precedence.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NotebookFrontmatter;
+
+#[derive(Deserialize)]
+struct Notebook {
+    #[serde(default)]
+    cells: Vec<NotebookCell>,
+    #[serde(default)]
+    metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct NotebookCell {
+    cell_type: String,
+    #[serde(default)]
+    source: NotebookSource,
+}
+
+/// Cell source, stored either as one string or as a list of lines
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NotebookSource {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl Default for NotebookSource {
+    fn default() -> Self {
+        NotebookSource::Text(String::new())
+    }
+}
+
+impl NotebookSource {
+    fn text(&self) -> String {
+        match self {
+            NotebookSource::Text(text) => text.clone(),
+            NotebookSource::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+impl FrontmatterExtractor for NotebookFrontmatter {
+    fn extract(&self, content: &str, config: &Config) -> Result<Option<serde_yaml::Mapping>> {
+        let notebook: Notebook =
+            serde_json::from_str(content).with_context(|| "invalid notebook JSON")?;
+
+        let mut mapping = serde_yaml::Mapping::new();
+        for key in ["title", config.root_key.as_str()] {
+            if let Some(value) = notebook.metadata.get(key) {
+                mapping.insert(key.into(), serde_yaml::to_value(value)?);
+            }
+        }
+
+        for (index, cell) in notebook.cells.iter().enumerate() {
+            if cell.cell_type != "raw" {
+                continue;
+            }
+            let source = cell.source.text();
+            if frontmatter::extract(&source).is_none() {
+                continue;
+            }
+            let cell_mapping = frontmatter::parse(&source)
+                .with_context(|| format!("in notebook cell {}", index + 1))?;
+            mapping.extend(cell_mapping.unwrap_or_default());
+            break;
+        }
+
+        Ok((!mapping.is_empty()).then_some(mapping))
+    }
+}
+
+/// YAML frontmatter in a comment at the top of a reStructuredText file
+///
+/// The comment body (indented below a `..` line) holds a `---` delimited
+/// block, which Sphinx and Docutils leave out of the rendered page:
+///
+/// ```rst
+/// ..
+///    ---
+///    title: Introduction
+///    course-map:
+///      id: intro
+///    ---
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RstFrontmatter;
+
+impl FrontmatterExtractor for RstFrontmatter {
+    fn extract(&self, content: &str, _config: &Config) -> Result<Option<serde_yaml::Mapping>> {
+        let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+        let mut lines = content
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .enumerate()
+            .skip_while(|(_, line)| line.trim().is_empty());
+
+        match lines.next() {
+            Some((_, line)) if line.trim_end() == ".." => {}
+            _ => return Ok(None),
+        }
+
+        // The comment ends at the first line that is not indented
+        let body: Vec<(usize, &str)> = lines
+            .take_while(|(_, line)| line.trim().is_empty() || line.starts_with([' ', '\t']))
+            .collect();
+        let Some(&(first_index, _)) = body.first() else {
+            return Ok(None);
+        };
+        let indent = body
+            .iter()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(_, line)| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let yaml: String = body
+            .iter()
+            .map(|(_, line)| format!("{}\n", line.get(indent..).unwrap_or("")))
+            .collect();
+
+        frontmatter::parse(&yaml).map_err(|mut e| {
+            // Report lines of the file rather than the comment body
+            e.line = e.line.map(|line| line + first_index);
+            e.into()
+        })
+    }
+}
+
+/// Frontmatter extractors by file extension
+///
+/// Extensions are matched case-insensitively. Files with an extension that
+/// has no extractor are read as YAML frontmatter.
+#[derive(Clone)]
+pub struct Extractors {
+    by_extension: HashMap<String, Arc<dyn FrontmatterExtractor>>,
+    fallback: Arc<dyn FrontmatterExtractor>,
+}
+
+impl Default for Extractors {
+    fn default() -> Self {
+        let mut extractors = Self {
+            by_extension: HashMap::new(),
+            fallback: Arc::new(YamlFrontmatter),
+        };
+        for extension in [
+            "qmd",
+            "md",
+            "markdown",
+            "rmd",
+            "rmarkdown",
+            "adoc",
+            "asciidoc",
+        ] {
+            extractors.register(extension, YamlFrontmatter);
+        }
+        extractors.register("ipynb", NotebookFrontmatter);
+        extractors.register("rst", RstFrontmatter);
+        extractors
+    }
+}
+
+impl Extractors {
+    /// Use `extractor` for files with `extension` (with or without a dot)
+    pub fn register<E: FrontmatterExtractor + 'static>(&mut self, extension: &str, extractor: E) {
+        self.by_extension
+            .insert(normalize_extension(extension), Arc::new(extractor));
+    }
+
+    /// The extractor for a file, chosen by its extension
+    pub fn for_path(&self, path: &Path) -> &dyn FrontmatterExtractor {
+        path.extension()
+            .and_then(|ext| {
+                self.by_extension
+                    .get(&normalize_extension(&ext.to_string_lossy()))
+            })
+            .unwrap_or(&self.fallback)
+            .as_ref()
+    }
+
+    /// The extractor used for each extension, as `ext=name` pairs in
+    /// extension order followed by the fallback
+    pub fn fingerprint(&self) -> String {
+        let mut pairs: Vec<String> = self
+            .by_extension
+            .iter()
+            .map(|(extension, extractor)| format!("{extension}={}", extractor.name()))
+            .collect();
+        pairs.sort();
+        pairs.push(format!("*={}", self.fallback.name()));
+        pairs.join(" ")
+    }
+}
+
+/// Lowercase an extension and drop a leading dot
+pub fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rst_frontmatter() -> Result<()> {
+        let config = Config::default();
+        let content =
+            "..\n   ---\n   title: Intro\n   course-map:\n     id: intro\n   ---\n\nIntro\n=====\n";
+        let mapping = RstFrontmatter.extract(content, &config)?.unwrap();
+        assert_eq!(mapping["title"], serde_yaml::Value::from("Intro"));
+        assert_eq!(
+            mapping["course-map"]["id"],
+            serde_yaml::Value::from("intro")
+        );
+
+        // A plain comment, or none at all, is not metadata
+        let content = ".. note to self\n\nIntro\n=====\n";
+        assert_eq!(RstFrontmatter.extract(content, &config)?, None);
+        assert_eq!(RstFrontmatter.extract("Intro\n=====\n", &config)?, None);
+
+        let content = "..\n   ---\n   title: [oops\n   ---\n";
+        let error = RstFrontmatter.extract(content, &config).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("invalid YAML frontmatter at line 4"),
+            "{error}"
+        );
+
+        Ok(())
+    }
+
+    struct Fixed;
+
+    impl FrontmatterExtractor for Fixed {
+        fn extract(&self, _content: &str, _config: &Config) -> Result<Option<serde_yaml::Mapping>> {
+            Ok(Some(serde_yaml::from_str("title: Fixed")?))
+        }
+    }
+
+    #[test]
+    fn test_registry() -> Result<()> {
+        let config = Config::default();
+        let mut extractors = Extractors::default();
+        extractors.register(".TEX", Fixed);
+
+        let content = "---\ntitle: Yaml\n---\n";
+        let title = |path: &str| -> Result<serde_yaml::Value> {
+            let mapping = extractors
+                .for_path(Path::new(path))
+                .extract(content, &config)?
+                .unwrap_or_default();
+            Ok(mapping.get("title").cloned().unwrap_or_default())
+        };
+
+        assert_eq!(title("notes.tex")?, serde_yaml::Value::from("Fixed"));
+        assert_eq!(title("lesson.Rmarkdown")?, serde_yaml::Value::from("Yaml"));
+        assert_eq!(title("lesson.unknown")?, serde_yaml::Value::from("Yaml"));
+        assert!(title("lesson.ipynb").is_err());
+
+        Ok(())
+    }
+}
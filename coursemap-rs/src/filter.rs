@@ -0,0 +1,447 @@
+//! Drawing part of a course
+//!
+//! A [`FilterConfig`] picks the courses to draw by phase, by frontmatter
+//! metadata ([`MetadataPredicate`]) and by path. The graph is built from
+//! every document first, so that prerequisites still resolve, and then
+//! [`filter_graph`] drops the other courses. Where dropped courses sat
+//! between kept ones, the kept courses are joined directly.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use crate::graph::{CourseGraph, CourseNode};
+
+/// Which courses to draw
+///
+/// A course is drawn when it passes every part of the filter; empty parts
+/// let everything through.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterConfig {
+    /// Phases to draw; empty for all
+    #[serde(default)]
+    pub phases: Vec<String>,
+    /// Phases to leave out
+    #[serde(default, rename = "exclude-phases")]
+    pub exclude_phases: Vec<String>,
+    /// Conditions on the frontmatter, all of which must hold
+    #[serde(default)]
+    pub metadata: Vec<MetadataPredicate>,
+    /// Path prefixes, relative to the input directory, of the documents to
+    /// draw; empty for all
+    #[serde(default)]
+    pub paths: Vec<PathBuf>,
+    /// Path prefixes of documents to leave out
+    #[serde(default, rename = "exclude-paths")]
+    pub exclude_paths: Vec<PathBuf>,
+}
+
+impl FilterConfig {
+    /// Whether the filter lets every course through
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a course is drawn
+    ///
+    /// Metadata keys are looked up in the frontmatter and then in its
+    /// `root_key` block; paths are relative to `root`.
+    pub fn matches(&self, node: &CourseNode, root_key: &str, root: &Path) -> bool {
+        if !self.phases.is_empty() && !self.phases.contains(&node.phase) {
+            return false;
+        }
+        if self.exclude_phases.contains(&node.phase) {
+            return false;
+        }
+        if !self
+            .metadata
+            .iter()
+            .all(|predicate| predicate.matches(node, root_key))
+        {
+            return false;
+        }
+
+        let path = normalize(node.file_path.strip_prefix(root).unwrap_or(&node.file_path));
+        let under = |prefix: &PathBuf| path.starts_with(normalize(prefix));
+        (self.paths.is_empty() || self.paths.iter().any(under))
+            && !self.exclude_paths.iter().any(under)
+    }
+}
+
+/// A path without `.` components, so that `./week1` matches `week1/intro.qmd`
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// A condition on a frontmatter value
+///
+/// Written as `KEY = VALUE` (or `KEY: VALUE`), `KEY != VALUE`,
+/// `KEY contains VALUE` or a bare `KEY`. Values are read as YAML, so
+/// `week = 3` and `week = "3"` both match `week: 3`. `KEY` may be a dotted
+/// path into nested mappings, such as `schedule.week`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataPredicate {
+    /// The value is set, and is neither `null` nor `false`
+    Present(String),
+    Equals(String, serde_yaml::Value),
+    NotEquals(String, serde_yaml::Value),
+    /// A list has an equal element, or a string contains the text
+    Contains(String, serde_yaml::Value),
+}
+
+impl MetadataPredicate {
+    pub fn key(&self) -> &str {
+        match self {
+            MetadataPredicate::Present(key)
+            | MetadataPredicate::Equals(key, _)
+            | MetadataPredicate::NotEquals(key, _)
+            | MetadataPredicate::Contains(key, _) => key,
+        }
+    }
+
+    /// Whether the predicate holds for a course (see [`FilterConfig::matches`])
+    pub fn matches(&self, node: &CourseNode, root_key: &str) -> bool {
+        let value = lookup(node, self.key(), root_key);
+        match self {
+            MetadataPredicate::Present(_) => !matches!(
+                value,
+                None | Some(serde_yaml::Value::Null) | Some(serde_yaml::Value::Bool(false))
+            ),
+            MetadataPredicate::Equals(_, expected) => value.is_some_and(|v| same(v, expected)),
+            MetadataPredicate::NotEquals(_, expected) => !value.is_some_and(|v| same(v, expected)),
+            MetadataPredicate::Contains(_, expected) => match value {
+                Some(serde_yaml::Value::Sequence(items)) => {
+                    items.iter().any(|item| same(item, expected))
+                }
+                Some(serde_yaml::Value::String(text)) => {
+                    scalar_text(expected).is_some_and(|part| text.contains(&part))
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+/// The value at a dotted key, in the frontmatter or else in its course map block
+fn lookup<'a>(node: &'a CourseNode, key: &str, root_key: &str) -> Option<&'a serde_yaml::Value> {
+    let mut parts = key.split('.');
+    let first = parts.next()?;
+    let rest: Vec<&str> = parts.collect();
+    let descend =
+        |value: &'a serde_yaml::Value| rest.iter().try_fold(value, |value, part| value.get(*part));
+
+    node.metadata.get(first).and_then(descend).or_else(|| {
+        let block = node.metadata.get(root_key)?;
+        descend(block.get(first)?)
+    })
+}
+
+/// Equal values, or scalars that read the same (`3` and `"3"`)
+fn same(value: &serde_yaml::Value, expected: &serde_yaml::Value) -> bool {
+    value == expected
+        || matches!(
+            (scalar_text(value), scalar_text(expected)),
+            (Some(a), Some(b)) if a == b
+        )
+}
+
+fn scalar_text(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(text) => Some(text.clone()),
+        serde_yaml::Value::Number(number) => Some(number.to_string()),
+        serde_yaml::Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+impl FromStr for MetadataPredicate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let value = |text: &str| -> Result<serde_yaml::Value> {
+            let text = text.trim();
+            if text.is_empty() {
+                bail!("Missing value in metadata condition: {s}");
+            }
+            Ok(serde_yaml::from_str(text).unwrap_or_else(|_| text.into()))
+        };
+        let key = |text: &str| -> Result<String> {
+            let text = text.trim();
+            if text.is_empty() || text.contains(char::is_whitespace) {
+                bail!("Invalid key in metadata condition: {s}");
+            }
+            Ok(text.to_string())
+        };
+
+        if let Some((k, v)) = s.split_once(" contains ") {
+            Ok(MetadataPredicate::Contains(key(k)?, value(v)?))
+        } else if let Some((k, v)) = s.split_once("!=") {
+            Ok(MetadataPredicate::NotEquals(key(k)?, value(v)?))
+        } else if let Some((k, v)) = s.split_once(['=', ':']) {
+            Ok(MetadataPredicate::Equals(key(k)?, value(v)?))
+        } else {
+            Ok(MetadataPredicate::Present(key(s)?))
+        }
+    }
+}
+
+impl fmt::Display for MetadataPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = |value: &serde_yaml::Value| {
+            scalar_text(value).unwrap_or_else(|| {
+                serde_yaml::to_string(value)
+                    .map(|yaml| yaml.trim_end().to_string())
+                    .unwrap_or_default()
+            })
+        };
+        match self {
+            MetadataPredicate::Present(key) => write!(f, "{key}"),
+            MetadataPredicate::Equals(key, value) => write!(f, "{key} = {}", text(value)),
+            MetadataPredicate::NotEquals(key, value) => write!(f, "{key} != {}", text(value)),
+            MetadataPredicate::Contains(key, value) => {
+                write!(f, "{key} contains {}", text(value))
+            }
+        }
+    }
+}
+
+impl Serialize for MetadataPredicate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MetadataPredicate {
+    /// A condition string, or a one-entry mapping such as `week: 3` (which
+    /// YAML reads an unquoted `- week: 3` list item as)
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        match serde_yaml::Value::deserialize(deserializer)? {
+            serde_yaml::Value::String(text) => text.parse().map_err(D::Error::custom),
+            serde_yaml::Value::Mapping(mapping) if mapping.len() == 1 => {
+                let (key, value) = mapping.into_iter().next().expect("one entry");
+                let key = scalar_text(&key)
+                    .ok_or_else(|| D::Error::custom("metadata keys must be strings"))?;
+                Ok(MetadataPredicate::Equals(key, value))
+            }
+            _ => Err(D::Error::custom(
+                "expected a metadata condition such as 'week = 3' or 'tags contains exam'",
+            )),
+        }
+    }
+}
+
+/// The courses of a graph that pass a filter
+///
+/// Paths between kept courses through dropped ones become edges (see
+/// [`CourseGraph::contract`]).
+pub fn filter_graph(
+    graph: &CourseGraph,
+    filter: &FilterConfig,
+    root_key: &str,
+    root: &Path,
+) -> CourseGraph {
+    let kept: HashSet<_> = graph
+        .nodes()
+        .filter(|(_, node)| filter.matches(node, root_key, root))
+        .map(|(index, _)| index)
+        .collect();
+    graph.contract(&kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DuplicateIds;
+    use crate::graph::{build_graph, EdgeKind};
+    use crate::parser::Document;
+    use std::collections::HashMap;
+
+    fn document(id: &str, phase: &str, prerequisites: &[&str], frontmatter: &str) -> Document {
+        Document::new(
+            id.to_string(),
+            id.to_string(),
+            PathBuf::from(format!("course/{phase}/{id}.qmd")),
+            phase.to_string(),
+            prerequisites.iter().map(|p| p.to_string()).collect(),
+            serde_yaml::from_str::<HashMap<String, serde_yaml::Value>>(frontmatter)
+                .unwrap_or_default(),
+        )
+    }
+
+    fn kept_ids(graph: &CourseGraph, filter: &FilterConfig) -> Vec<String> {
+        filter_graph(graph, filter, "course-map", Path::new("course"))
+            .nodes()
+            .map(|(_, node)| node.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_predicates() -> Result<()> {
+        assert_eq!(
+            "week = 3".parse::<MetadataPredicate>()?,
+            MetadataPredicate::Equals("week".to_string(), 3.into())
+        );
+        assert_eq!(
+            "week: 3".parse::<MetadataPredicate>()?,
+            MetadataPredicate::Equals("week".to_string(), 3.into())
+        );
+        assert_eq!(
+            r#"tags contains "exam""#.parse::<MetadataPredicate>()?,
+            MetadataPredicate::Contains("tags".to_string(), "exam".into())
+        );
+        assert_eq!(
+            "level != advanced".parse::<MetadataPredicate>()?,
+            MetadataPredicate::NotEquals("level".to_string(), "advanced".into())
+        );
+        assert_eq!(
+            "graded".parse::<MetadataPredicate>()?,
+            MetadataPredicate::Present("graded".to_string())
+        );
+        assert!("week =".parse::<MetadataPredicate>().is_err());
+        assert!("a b".parse::<MetadataPredicate>().is_err());
+
+        let filter: FilterConfig = serde_yaml::from_str(
+            "phases: [Pre]\nmetadata:\n  - week: 3\n  - tags contains exam\npaths: [week1/]\n",
+        )?;
+        assert_eq!(
+            filter.metadata,
+            vec![
+                MetadataPredicate::Equals("week".to_string(), 3.into()),
+                MetadataPredicate::Contains("tags".to_string(), "exam".into()),
+            ]
+        );
+        assert_eq!(
+            filter.metadata[1].to_string(),
+            "tags contains exam".to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_by_phase_metadata_and_path() -> Result<()> {
+        let documents = vec![
+            document("intro", "Pre", &[], "week: 1\ntags: [basics]"),
+            document("micro", "InClass", &["intro"], "week: 3\ntags: [exam]"),
+            document(
+                "macro",
+                "InClass",
+                &["intro"],
+                "course-map:\n  week: \"3\"\nschedule:\n  room: B",
+            ),
+            document("advanced", "Post", &["micro"], "tags: [exam, project]"),
+        ];
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+
+        let by_phase = FilterConfig {
+            phases: vec!["InClass".to_string(), "Post".to_string()],
+            exclude_phases: vec!["Post".to_string()],
+            ..FilterConfig::default()
+        };
+        assert_eq!(kept_ids(&graph, &by_phase), vec!["micro", "macro"]);
+
+        let by_week = FilterConfig {
+            metadata: vec!["week = 3".parse()?],
+            ..FilterConfig::default()
+        };
+        assert_eq!(kept_ids(&graph, &by_week), vec!["micro", "macro"]);
+
+        let by_tag = FilterConfig {
+            metadata: vec!["tags contains exam".parse()?, "week != 3".parse()?],
+            ..FilterConfig::default()
+        };
+        assert_eq!(kept_ids(&graph, &by_tag), vec!["advanced"]);
+
+        let nested = FilterConfig {
+            metadata: vec!["schedule.room".parse()?],
+            ..FilterConfig::default()
+        };
+        assert_eq!(kept_ids(&graph, &nested), vec!["macro"]);
+
+        let by_path = FilterConfig {
+            paths: vec![PathBuf::from("./InClass")],
+            exclude_paths: vec![PathBuf::from("InClass/macro.qmd")],
+            ..FilterConfig::default()
+        };
+        assert_eq!(kept_ids(&graph, &by_path), vec!["micro"]);
+
+        assert!(FilterConfig::default().is_empty());
+        assert_eq!(kept_ids(&graph, &FilterConfig::default()).len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_keeps_transitive_edges() -> Result<()> {
+        let mut advanced = document("advanced", "Post", &["micro"], "");
+        advanced.recommended = vec!["macro".to_string()];
+        let documents = vec![
+            document("intro", "Pre", &[], ""),
+            document("micro", "InClass", &["intro"], ""),
+            document("macro", "InClass", &["intro"], ""),
+            advanced,
+        ];
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+
+        let filter = FilterConfig {
+            exclude_phases: vec!["InClass".to_string()],
+            ..FilterConfig::default()
+        };
+        let filtered = filter_graph(&graph, &filter, "course-map", Path::new("course"));
+
+        // intro -> micro -> advanced outranks intro -> macro ~> advanced
+        let edges: Vec<(&str, &str, EdgeKind)> = filtered
+            .typed_edges()
+            .map(|(source, target, kind)| {
+                (
+                    filtered.graph[source].id.as_str(),
+                    filtered.graph[target].id.as_str(),
+                    kind,
+                )
+            })
+            .collect();
+        assert_eq!(edges, vec![("intro", "advanced", EdgeKind::Required)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_upgrades_direct_edges() -> Result<()> {
+        let mut advanced = document("advanced", "Post", &["micro"], "");
+        advanced.recommended = vec!["intro".to_string()];
+        let documents = vec![
+            document("intro", "Pre", &[], ""),
+            document("micro", "InClass", &["intro"], ""),
+            advanced,
+        ];
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+
+        let filter = FilterConfig {
+            exclude_phases: vec!["InClass".to_string()],
+            ..FilterConfig::default()
+        };
+        let filtered = filter_graph(&graph, &filter, "course-map", Path::new("course"));
+
+        // intro ~> advanced becomes required rather than gaining a twin
+        let edges: Vec<(&str, &str, EdgeKind)> = filtered
+            .typed_edges()
+            .map(|(source, target, kind)| {
+                (
+                    filtered.graph[source].id.as_str(),
+                    filtered.graph[target].id.as_str(),
+                    kind,
+                )
+            })
+            .collect();
+        assert_eq!(edges, vec![("intro", "advanced", EdgeKind::Required)]);
+
+        Ok(())
+    }
+}
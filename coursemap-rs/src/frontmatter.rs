@@ -0,0 +1,199 @@
+//! YAML frontmatter extraction for Markdown and Quarto documents
+//!
+//! Follows the Pandoc rules for a metadata block at the top of a file: an
+//! optional byte order mark and blank lines, an opening `---` line that is
+//! not followed by a blank line, and a closing `---` or `...` line. Trailing
+//! whitespace and CRLF line endings are accepted on the delimiters. A
+//! document without a complete block (e.g. one that merely contains `---`
+//! horizontal rules) has no frontmatter.
+
+use std::error::Error;
+use std::fmt;
+
+/// The raw YAML of a frontmatter block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frontmatter {
+    /// YAML text between the delimiters, with `\n` line endings
+    pub yaml: String,
+    /// 1-based line in the file where the YAML starts
+    pub first_line: usize,
+}
+
+/// Malformed YAML in a frontmatter block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontmatterError {
+    /// 1-based line in the file, if the YAML parser reported one
+    pub line: Option<usize>,
+    /// 1-based column, if the YAML parser reported one
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for FrontmatterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "invalid YAML frontmatter at line {line}, column {column}: {}",
+                self.message
+            ),
+            _ => write!(f, "invalid YAML frontmatter: {}", self.message),
+        }
+    }
+}
+
+impl Error for FrontmatterError {}
+
+/// Find the frontmatter block at the top of `content`
+pub fn extract(content: &str) -> Option<Frontmatter> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = content
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .enumerate();
+
+    // Skip leading blank lines; the first other line must open the block
+    let (opening, first) = lines.find(|(_, line)| !line.trim().is_empty())?;
+    if first.trim_end() != "---" {
+        return None;
+    }
+
+    let mut yaml = String::new();
+    for (index, line) in lines {
+        let delimiter = line.trim_end();
+        if delimiter == "---" || delimiter == "..." {
+            return Some(Frontmatter {
+                yaml,
+                first_line: opening + 2,
+            });
+        }
+        // `---` followed by a blank line is a horizontal rule
+        if index == opening + 1 && line.trim().is_empty() {
+            return None;
+        }
+        yaml.push_str(line);
+        yaml.push('\n');
+    }
+
+    None
+}
+
+/// Extract and parse the frontmatter of `content`
+///
+/// Returns `Ok(None)` when there is no frontmatter block, or when the block
+/// does not hold a YAML mapping (such as a Setext heading between rules).
+/// An empty block is an empty mapping.
+pub fn parse(content: &str) -> Result<Option<serde_yaml::Mapping>, FrontmatterError> {
+    let Some(frontmatter) = extract(content) else {
+        return Ok(None);
+    };
+
+    match serde_yaml::from_str::<serde_yaml::Value>(&frontmatter.yaml) {
+        Ok(serde_yaml::Value::Mapping(mapping)) => Ok(Some(mapping)),
+        Ok(serde_yaml::Value::Null) => Ok(Some(serde_yaml::Mapping::new())),
+        Ok(_) => Ok(None),
+        Err(e) => {
+            let location = e.location();
+            Err(FrontmatterError {
+                line: location
+                    .as_ref()
+                    .map(|l| frontmatter.first_line + l.line() - 1),
+                column: location.as_ref().map(|l| l.column()),
+                message: strip_locations(&e.to_string()),
+            })
+        }
+    }
+}
+
+/// Remove serde_yaml's "at line N column M" notes, which count lines from
+/// the start of the block rather than the file
+fn strip_locations(message: &str) -> String {
+    let mut stripped = String::new();
+    let mut rest = message;
+
+    while let Some(start) = rest.find(" at line ") {
+        stripped.push_str(&rest[..start]);
+        let after = &rest[start + " at line ".len()..];
+        let after = after.trim_start_matches(|c: char| c.is_ascii_digit());
+        let after = after.strip_prefix(" column ").unwrap_or(after);
+        rest = after.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    stripped.push_str(rest);
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(content: &str) -> Option<String> {
+        extract(content).map(|f| f.yaml)
+    }
+
+    #[test]
+    fn test_extract_basic() {
+        let frontmatter = extract("---\ntitle: A\n---\n# Body\n").unwrap();
+        assert_eq!(frontmatter.yaml, "title: A\n");
+        assert_eq!(frontmatter.first_line, 2);
+    }
+
+    #[test]
+    fn test_extract_edge_cases() {
+        // Byte order mark and leading blank lines
+        assert_eq!(
+            extract("\u{feff}\n\n---\ntitle: A\n---\n"),
+            Some(Frontmatter {
+                yaml: "title: A\n".to_string(),
+                first_line: 4,
+            })
+        );
+        // YAML document end marker
+        assert_eq!(
+            yaml("---\ntitle: A\n...\nBody\n"),
+            Some("title: A\n".into())
+        );
+        // CRLF endings with trailing spaces on the delimiters
+        assert_eq!(
+            yaml("--- \r\ntitle: A\r\n---  \r\nBody\r\n"),
+            Some("title: A\n".into())
+        );
+        // Empty block
+        assert_eq!(yaml("---\n---\n"), Some(String::new()));
+    }
+
+    #[test]
+    fn test_extract_without_frontmatter() {
+        assert_eq!(yaml("# Title\n\n---\n\nMore text\n---\n"), None);
+        assert_eq!(yaml("---\n\nAfter a rule\n\n---\n"), None);
+        assert_eq!(yaml("---\ntitle: never closed\n"), None);
+        assert_eq!(yaml("----\ntitle: A\n----\n"), None);
+        assert_eq!(yaml(""), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let mapping = parse("---\ntitle: A\n---\n").unwrap().unwrap();
+        assert_eq!(mapping["title"], serde_yaml::Value::from("A"));
+
+        assert_eq!(
+            parse("---\n---\n").unwrap(),
+            Some(serde_yaml::Mapping::new())
+        );
+        // A Setext heading between two rules is not metadata
+        assert_eq!(parse("---\nJust a heading\n---\n").unwrap(), None);
+        assert_eq!(parse("No frontmatter\n").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_reports_location() {
+        let error = parse("\n---\ntitle: A\ncourse-map: [unclosed\n---\n").unwrap_err();
+        assert_eq!(error.line, Some(5));
+        assert_eq!(error.column, Some(1));
+        assert_eq!(
+            error.to_string(),
+            "invalid YAML frontmatter at line 5, column 1: \
+             did not find expected ',' or ']', while parsing a flow sequence"
+        );
+    }
+}
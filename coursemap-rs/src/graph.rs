@@ -1,14 +1,71 @@
 //! Graph construction and manipulation for course dependencies
 
 use anyhow::{Context, Result};
-use petgraph::graph::{DiGraph, NodeIndex};
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
 
+use crate::config::{DuplicateIds, FocusDirection, LinksConfig};
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Severity};
 use crate::parser::Document;
 
+/// How a course depends on another
+///
+/// Edges always point from the course that is depended on to the dependent
+/// course, i.e. from an entry in the frontmatter list to the document that
+/// lists it. Corequisites have no real direction; they are drawn without
+/// one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EdgeKind {
+    /// Must be taken first (`prerequisites`)
+    #[default]
+    Required,
+    /// Worth taking first (`recommended`)
+    Recommended,
+    /// Taken together (`corequisites`)
+    Corequisite,
+}
+
+impl EdgeKind {
+    pub const ALL: [EdgeKind; 3] = [
+        EdgeKind::Required,
+        EdgeKind::Recommended,
+        EdgeKind::Corequisite,
+    ];
+
+    /// The kinds that put courses in an order, used for cycle detection
+    pub const ORDERING: [EdgeKind; 2] = [EdgeKind::Required, EdgeKind::Recommended];
+}
+
+impl fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeKind::Required => write!(f, "required"),
+            EdgeKind::Recommended => write!(f, "recommended"),
+            EdgeKind::Corequisite => write!(f, "corequisite"),
+        }
+    }
+}
+
+/// An edge implied by other edges of the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedundantEdge {
+    pub edge: EdgeIndex,
+    pub source: NodeIndex,
+    pub target: NodeIndex,
+    pub kind: EdgeKind,
+    /// Another direct prerequisite of `target` that depends on `source`
+    pub via: NodeIndex,
+}
+
 #[derive(Debug, Clone)]
 pub struct CourseGraph {
-    pub graph: DiGraph<CourseNode, ()>,
+    pub graph: DiGraph<CourseNode, EdgeKind>,
     pub node_map: HashMap<String, NodeIndex>,
 }
 
@@ -18,6 +75,14 @@ pub struct CourseNode {
     pub title: String,
     pub phase: String,
     pub display_name: String,
+    pub file_path: PathBuf,
+    pub metadata: HashMap<String, serde_yaml::Value>,
+    /// Link to the rendered page, set by [`CourseGraph::set_page_urls`]
+    pub url: Option<String>,
+    /// Position in the project's navigation, used as a layout hint
+    pub order: Option<usize>,
+    /// Course the node belongs to, in a map of several courses
+    pub namespace: Option<String>,
 }
 
 impl CourseNode {
@@ -27,6 +92,11 @@ impl CourseNode {
             title: doc.title.clone(),
             phase: doc.phase.clone(),
             display_name: doc.display_name(),
+            file_path: doc.file_path.clone(),
+            metadata: doc.metadata.clone(),
+            url: None,
+            order: doc.order,
+            namespace: doc.namespace.clone(),
         }
     }
 }
@@ -62,8 +132,22 @@ impl CourseGraph {
         }
     }
 
-    /// Add an edge between two nodes (prerequisite -> course)
+    /// Add a required edge between two nodes (prerequisite -> course)
     pub fn add_edge(&mut self, prerequisite_id: &str, course_id: &str) -> Result<()> {
+        self.add_edge_of_kind(prerequisite_id, course_id, EdgeKind::Required)
+    }
+
+    /// Add an edge of the given kind between two nodes (prerequisite -> course)
+    ///
+    /// An edge that is already in the graph is not added again; for
+    /// corequisites this includes the edge in the opposite direction, so
+    /// two courses that list each other are joined once.
+    pub fn add_edge_of_kind(
+        &mut self,
+        prerequisite_id: &str,
+        course_id: &str,
+        kind: EdgeKind,
+    ) -> Result<()> {
         let prerequisite_index = self
             .node_map
             .get(prerequisite_id)
@@ -76,7 +160,16 @@ impl CourseGraph {
             .copied()
             .with_context(|| format!("Course node not found: {course_id}"))?;
 
-        self.graph.add_edge(prerequisite_index, course_index, ());
+        let exists = |source, target| {
+            self.graph
+                .edges_connecting(source, target)
+                .any(|edge| *edge.weight() == kind)
+        };
+        let duplicate = exists(prerequisite_index, course_index)
+            || (kind == EdgeKind::Corequisite && exists(course_index, prerequisite_index));
+        if !duplicate {
+            self.graph.add_edge(prerequisite_index, course_index, kind);
+        }
         Ok(())
     }
 
@@ -102,15 +195,409 @@ impl CourseGraph {
         })
     }
 
+    /// Get all edges in the graph with their kind
+    pub fn typed_edges(&self) -> impl Iterator<Item = (NodeIndex, NodeIndex, EdgeKind)> + '_ {
+        self.graph
+            .edge_references()
+            .map(|edge| (edge.source(), edge.target(), *edge.weight()))
+    }
+
+    /// A copy of the graph with only the edges of the given kinds
+    ///
+    /// Every node is kept with the same index, so results of analyses on
+    /// the copy (cycles, topological order, roots, ...) apply to this graph.
+    pub fn with_edge_kinds(&self, kinds: &[EdgeKind]) -> CourseGraph {
+        CourseGraph {
+            graph: self.graph.filter_map(
+                |_, node| Some(node.clone()),
+                |_, kind| kinds.contains(kind).then_some(*kind),
+            ),
+            node_map: self.node_map.clone(),
+        }
+    }
+
+    /// Nodes grouped by course namespace, in order of first appearance
+    ///
+    /// Nodes without a namespace form a group of their own, named `None`.
+    pub fn courses(&self) -> Vec<(Option<&str>, Vec<NodeIndex>)> {
+        let mut courses: Vec<(Option<&str>, Vec<NodeIndex>)> = Vec::new();
+        for (index, node) in self.nodes() {
+            let namespace = node.namespace.as_deref();
+            match courses.iter_mut().find(|(course, _)| *course == namespace) {
+                Some((_, nodes)) => nodes.push(index),
+                None => courses.push((namespace, vec![index])),
+            }
+        }
+        courses
+    }
+
+    /// Point every node at its rendered page
+    ///
+    /// The URL is computed by [`LinksConfig::url_for`] from the node's source
+    /// file relative to `root`. With the default links this is where Quarto
+    /// puts the page when rendering a website from `root`.
+    pub fn set_page_urls(&mut self, root: &Path, links: &LinksConfig) {
+        for node in self.graph.node_weights_mut() {
+            let relative = node.file_path.strip_prefix(root).unwrap_or(&node.file_path);
+            node.url = Some(links.url_for(relative));
+        }
+    }
+
     /// Check if the graph has cycles
+    ///
+    /// Like the other cycle analyses, this only follows required and
+    /// recommended edges: corequisites are taken together, so they never
+    /// close a cycle.
     pub fn has_cycles(&self) -> bool {
-        petgraph::algo::is_cyclic_directed(&self.graph)
+        petgraph::algo::is_cyclic_directed(&self.with_edge_kinds(&EdgeKind::ORDERING).graph)
+    }
+
+    /// Get the strongly connected components that contain a cycle
+    ///
+    /// Each component is a group of courses that all (transitively) require
+    /// each other. Nodes within a component are sorted by index, and the
+    /// components are ordered by their first node.
+    pub fn cyclic_components(&self) -> Vec<Vec<NodeIndex>> {
+        let ordering = self.with_edge_kinds(&EdgeKind::ORDERING);
+        let mut components: Vec<Vec<NodeIndex>> = petgraph::algo::tarjan_scc(&ordering.graph)
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || ordering.graph.contains_edge(component[0], component[0])
+            })
+            .map(|mut component| {
+                component.sort();
+                component
+            })
+            .collect();
+        components.sort();
+        components
+    }
+
+    /// Find one explicit cycle in each cyclic component
+    ///
+    /// A cycle `[a, b, c]` stands for `a -> b -> c -> a`; it is the shortest
+    /// cycle through the first node of its component.
+    pub fn find_cycles(&self) -> Vec<Vec<NodeIndex>> {
+        self.cyclic_components()
+            .iter()
+            .map(|component| self.shortest_cycle_within(component))
+            .collect()
+    }
+
+    /// Breadth-first search from the first node of a component back to itself
+    fn shortest_cycle_within(&self, component: &[NodeIndex]) -> Vec<NodeIndex> {
+        let start = component[0];
+        let members: HashSet<NodeIndex> = component.iter().copied().collect();
+        let mut parent: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            let ordering_edges = self
+                .graph
+                .edges(node)
+                .filter(|edge| EdgeKind::ORDERING.contains(edge.weight()));
+            for next in ordering_edges.map(|edge| edge.target()) {
+                if next == start {
+                    let mut path = vec![node];
+                    let mut current = node;
+                    while current != start {
+                        current = parent[&current];
+                        path.push(current);
+                    }
+                    path.reverse();
+                    return path;
+                }
+                if members.contains(&next) && !parent.contains_key(&next) {
+                    parent.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        // Unreachable for a genuine cyclic component
+        component.to_vec()
+    }
+
+    /// Get the required and recommended edges that lie on a cycle (both ends
+    /// in the same cyclic component)
+    pub fn cycle_edges(&self) -> HashSet<(NodeIndex, NodeIndex)> {
+        let mut component_of: HashMap<NodeIndex, usize> = HashMap::new();
+        for (i, component) in self.cyclic_components().into_iter().enumerate() {
+            for node in component {
+                component_of.insert(node, i);
+            }
+        }
+
+        self.typed_edges()
+            .filter(|(source, target, kind)| {
+                EdgeKind::ORDERING.contains(kind)
+                    && matches!(
+                        (component_of.get(source), component_of.get(target)),
+                        (Some(a), Some(b)) if a == b
+                    )
+            })
+            .map(|(source, target, _)| (source, target))
+            .collect()
+    }
+
+    /// Format a cycle as `a -> b -> c -> a`
+    pub fn format_cycle(&self, cycle: &[NodeIndex]) -> String {
+        cycle
+            .iter()
+            .chain(cycle.first())
+            .map(|&idx| self.graph[idx].id.as_str())
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+
+    /// Find the edges that are implied by a longer path
+    ///
+    /// A required edge `a -> c` is redundant when `c` has another required
+    /// prerequisite `b` that (transitively) requires `a`. A recommended edge
+    /// is also redundant when the path uses recommended edges. Corequisites
+    /// are never redundant, and neither are edges that are only implied
+    /// through a cycle at either end: courses in a cycle imply each other's
+    /// edges, so removing every redundant edge at once could drop them all.
+    pub fn redundant_edges(&self) -> Vec<RedundantEdge> {
+        let mut component_of = vec![0; self.node_count()];
+        for (i, component) in petgraph::algo::tarjan_scc(&self.graph)
+            .into_iter()
+            .enumerate()
+        {
+            for node in component {
+                component_of[node.index()] = i;
+            }
+        }
+
+        let mut redundant = Vec::new();
+        for edge in self.graph.edge_references() {
+            let (source, target, kind) = (edge.source(), edge.target(), *edge.weight());
+            let follows = |other: EdgeKind| match kind {
+                EdgeKind::Required => other == EdgeKind::Required,
+                EdgeKind::Recommended => other != EdgeKind::Corequisite,
+                EdgeKind::Corequisite => false,
+            };
+
+            // Walk backwards from the course's other prerequisites
+            let via = self
+                .graph
+                .edges_directed(target, petgraph::Direction::Incoming)
+                .filter(|other| other.id() != edge.id() && follows(*other.weight()))
+                .map(|other| other.source())
+                .filter(|&via| {
+                    via != source
+                        && component_of[via.index()] != component_of[target.index()]
+                        && component_of[via.index()] != component_of[source.index()]
+                })
+                .find(|&via| self.depends_on(via, source, &follows));
+
+            if let Some(via) = via {
+                redundant.push(RedundantEdge {
+                    edge: edge.id(),
+                    source,
+                    target,
+                    kind,
+                    via,
+                });
+            }
+        }
+
+        redundant
+    }
+
+    /// Whether `course` transitively depends on `prerequisite` through edges
+    /// whose kind passes `follows`
+    fn depends_on(
+        &self,
+        course: NodeIndex,
+        prerequisite: NodeIndex,
+        follows: &impl Fn(EdgeKind) -> bool,
+    ) -> bool {
+        let mut visited = HashSet::from([course]);
+        let mut stack = vec![course];
+        while let Some(node) = stack.pop() {
+            for edge in self
+                .graph
+                .edges_directed(node, petgraph::Direction::Incoming)
+            {
+                if !follows(*edge.weight()) {
+                    continue;
+                }
+                if edge.source() == prerequisite {
+                    return true;
+                }
+                if visited.insert(edge.source()) {
+                    stack.push(edge.source());
+                }
+            }
+        }
+        false
+    }
+
+    /// A copy of the graph without its redundant edges (see
+    /// [`CourseGraph::redundant_edges`])
+    ///
+    /// Every node is kept with the same index, and every course still
+    /// depends on the same courses as before.
+    pub fn transitive_reduction(&self) -> CourseGraph {
+        let redundant: HashSet<EdgeIndex> = self.redundant_edges().iter().map(|r| r.edge).collect();
+        CourseGraph {
+            graph: self.graph.filter_map(
+                |_, node| Some(node.clone()),
+                |edge, kind| (!redundant.contains(&edge)).then_some(*kind),
+            ),
+            node_map: self.node_map.clone(),
+        }
+    }
+
+    /// A copy of the graph with only the given nodes and the edges between them
+    ///
+    /// Nodes keep their relative order, but are renumbered.
+    pub fn subgraph(&self, nodes: &HashSet<NodeIndex>) -> CourseGraph {
+        let graph = self.graph.filter_map(
+            |index, node| nodes.contains(&index).then(|| node.clone()),
+            |_, kind| Some(*kind),
+        );
+        let node_map = graph
+            .node_indices()
+            .map(|index| (graph[index].id.clone(), index))
+            .collect();
+        CourseGraph { graph, node_map }
+    }
+
+    /// A copy of the graph with only the given nodes, joining the ones that
+    /// were connected through the others
+    ///
+    /// When a path of required and recommended edges leads from one kept
+    /// node to another through dropped nodes only, the two are joined by a
+    /// required edge, or a recommended one if the path (and every other such
+    /// path) has a recommended edge. Where the two already share a direct
+    /// edge, that edge is upgraded to required if the path is required
+    /// instead of being doubled, and no node is joined to itself. Nodes keep
+    /// their relative order, but are renumbered.
+    pub fn contract(&self, nodes: &HashSet<NodeIndex>) -> CourseGraph {
+        let mut contracted = self.clone();
+        for source in self.graph.node_indices().filter(|n| nodes.contains(n)) {
+            let mut joined: BTreeMap<NodeIndex, EdgeKind> = BTreeMap::new();
+            let mut visited = HashSet::new();
+            let mut stack = vec![(source, EdgeKind::Required)];
+            while let Some((node, path_kind)) = stack.pop() {
+                for edge in self.graph.edges(node) {
+                    if !EdgeKind::ORDERING.contains(edge.weight()) {
+                        continue;
+                    }
+                    let kind = path_kind.max(*edge.weight());
+                    let next = edge.target();
+                    if !nodes.contains(&next) {
+                        if visited.insert((next, kind)) {
+                            stack.push((next, kind));
+                        }
+                    } else if node != source && next != source {
+                        joined
+                            .entry(next)
+                            .and_modify(|joined| *joined = (*joined).min(kind))
+                            .or_insert(kind);
+                    }
+                }
+            }
+
+            for (target, kind) in joined {
+                let direct = self
+                    .graph
+                    .edges_connecting(source, target)
+                    .filter(|edge| EdgeKind::ORDERING.contains(edge.weight()))
+                    .min_by_key(|edge| *edge.weight())
+                    .map(|edge| (edge.id(), *edge.weight()));
+                match direct {
+                    Some((edge, direct_kind)) if direct_kind > kind => {
+                        contracted.graph[edge] = kind;
+                    }
+                    Some(_) => {}
+                    None => {
+                        contracted.graph.add_edge(source, target, kind);
+                    }
+                }
+            }
+        }
+        contracted.subgraph(nodes)
+    }
+
+    /// The courses within `depth` steps of a course, including itself
+    ///
+    /// Upstream courses are the ones it (transitively) depends on through
+    /// required and recommended edges, downstream courses the ones that
+    /// depend on it. Without a depth every such course is included.
+    pub fn neighbourhood(
+        &self,
+        index: NodeIndex,
+        direction: FocusDirection,
+        depth: Option<usize>,
+    ) -> HashSet<NodeIndex> {
+        let mut nodes = HashSet::from([index]);
+        let sides = [
+            (direction.upstream(), petgraph::Direction::Incoming),
+            (direction.downstream(), petgraph::Direction::Outgoing),
+        ];
+        for (_, side) in sides.into_iter().filter(|(wanted, _)| *wanted) {
+            let mut visited = HashSet::from([index]);
+            let mut queue = VecDeque::from([(index, 0)]);
+            while let Some((node, distance)) = queue.pop_front() {
+                if depth.is_some_and(|depth| distance >= depth) {
+                    continue;
+                }
+                for edge in self.graph.edges_directed(node, side) {
+                    if !EdgeKind::ORDERING.contains(edge.weight()) {
+                        continue;
+                    }
+                    let next = match side {
+                        petgraph::Direction::Incoming => edge.source(),
+                        petgraph::Direction::Outgoing => edge.target(),
+                    };
+                    if visited.insert(next) {
+                        queue.push_back((next, distance + 1));
+                    }
+                }
+            }
+            nodes.extend(visited);
+        }
+        nodes
+    }
+
+    /// The part of the graph around one course: what it needs first, what it
+    /// unlocks, or both (see [`CourseGraph::neighbourhood`])
+    ///
+    /// Every edge between the remaining courses is kept, corequisites
+    /// included. Fails if no course has the id.
+    pub fn focus(
+        &self,
+        id: &str,
+        direction: FocusDirection,
+        depth: Option<usize>,
+    ) -> Result<CourseGraph> {
+        let index = self
+            .node_map
+            .get(id)
+            .copied()
+            .with_context(|| format!("Cannot focus on unknown course '{id}'"))?;
+        Ok(self.subgraph(&self.neighbourhood(index, direction, depth)))
     }
 
     /// Get nodes in topological order (if the graph is acyclic)
+    ///
+    /// Corequisites impose no order on each other.
     pub fn topological_sort(&self) -> Result<Vec<NodeIndex>> {
-        petgraph::algo::toposort(&self.graph, None)
-            .map_err(|_| anyhow::anyhow!("Graph contains cycles, cannot perform topological sort"))
+        let ordering = self.with_edge_kinds(&EdgeKind::ORDERING);
+        petgraph::algo::toposort(&ordering.graph, None).map_err(|_| {
+            let cycles: Vec<String> = self
+                .find_cycles()
+                .iter()
+                .map(|cycle| self.format_cycle(cycle))
+                .collect();
+            anyhow::anyhow!(
+                "Graph contains cycles, cannot perform topological sort: {}",
+                cycles.join("; ")
+            )
+        })
     }
 
     /// Get the number of nodes
@@ -151,38 +638,150 @@ impl CourseGraph {
 }
 
 /// Build a course dependency graph from a list of documents
-pub fn build_graph(documents: Vec<Document>) -> Result<CourseGraph> {
+///
+/// Problems such as missing prerequisites or cycles don't prevent the graph
+/// from being built; they are returned as diagnostics alongside it. When
+/// several documents declare the same id, `duplicate_ids` decides which one
+/// becomes the node; the others are dropped entirely.
+pub fn build_graph(
+    documents: Vec<Document>,
+    duplicate_ids: DuplicateIds,
+) -> Result<(CourseGraph, Vec<Diagnostic>)> {
     let mut graph = CourseGraph::new();
+    let mut diagnostics = Vec::new();
+
+    let documents = resolve_duplicate_ids(documents, duplicate_ids, &mut diagnostics);
 
     // First pass: add all nodes
     for doc in &documents {
         graph.add_node(doc);
     }
 
-    // Second pass: add edges based on prerequisites
+    // Second pass: add edges based on prerequisites, recommendations and
+    // corequisites
     for doc in &documents {
-        for prerequisite in &doc.prerequisites {
-            // Check if prerequisite exists as a node
-            if graph.node_map.contains_key(prerequisite) {
-                graph.add_edge(prerequisite, &doc.id).with_context(|| {
-                    format!("Failed to add edge from {} to {}", prerequisite, doc.id)
-                })?;
-            } else {
-                // Log warning about missing prerequisite
-                eprintln!(
-                    "Warning: Prerequisite '{}' for course '{}' not found in documents",
-                    prerequisite, doc.id
-                );
+        for (kind, listed) in doc.dependencies() {
+            for prerequisite in listed {
+                if prerequisite == &doc.id {
+                    diagnostics.push(Diagnostic::error(
+                        DiagnosticKind::SelfLoop { id: doc.id.clone() },
+                        vec![doc.file_path.clone()],
+                    ));
+                } else if graph.node_map.contains_key(prerequisite) {
+                    graph
+                        .add_edge_of_kind(prerequisite, &doc.id, kind)
+                        .with_context(|| {
+                            format!("Failed to add edge from {} to {}", prerequisite, doc.id)
+                        })?;
+                } else {
+                    diagnostics.push(Diagnostic::warning(
+                        DiagnosticKind::MissingPrerequisite {
+                            course: doc.id.clone(),
+                            prerequisite: prerequisite.clone(),
+                            edge: kind,
+                        },
+                        vec![doc.file_path.clone()],
+                    ));
+                }
             }
         }
     }
 
-    // Check for cycles
-    if graph.has_cycles() {
-        eprintln!("Warning: The course dependency graph contains cycles");
+    // Check for cycles, reporting one explicit path per group of courses
+    // that require each other. Corequisites are taken together, so they
+    // don't take part.
+    for (component, cycle) in graph.cyclic_components().iter().zip(graph.find_cycles()) {
+        let ids = |nodes: &[NodeIndex]| -> Vec<String> {
+            nodes
+                .iter()
+                .map(|&idx| graph.graph[idx].id.clone())
+                .collect()
+        };
+        diagnostics.push(Diagnostic::error(
+            DiagnosticKind::Cycle {
+                path: ids(&cycle),
+                members: ids(component),
+            },
+            component
+                .iter()
+                .map(|&idx| graph.graph[idx].file_path.clone())
+                .collect(),
+        ));
+    }
+
+    Ok((graph, diagnostics))
+}
+
+/// Remove the redundant edges of a graph, reporting each one as a warning
+///
+/// The warnings point at the document that lists the redundant prerequisite,
+/// so authors can remove it from the frontmatter.
+pub fn reduce_graph(graph: &CourseGraph) -> (CourseGraph, Vec<Diagnostic>) {
+    let diagnostics = graph
+        .redundant_edges()
+        .into_iter()
+        .map(|redundant| {
+            let course = &graph.graph[redundant.target];
+            Diagnostic::warning(
+                DiagnosticKind::RedundantPrerequisite {
+                    course: course.id.clone(),
+                    prerequisite: graph.graph[redundant.source].id.clone(),
+                    via: graph.graph[redundant.via].id.clone(),
+                    edge: redundant.kind,
+                },
+                vec![course.file_path.clone()],
+            )
+        })
+        .collect();
+
+    (graph.transitive_reduction(), diagnostics)
+}
+
+/// Keep one document per id according to the policy, reporting every id
+/// that is declared more than once together with all of its files
+fn resolve_duplicate_ids(
+    documents: Vec<Document>,
+    policy: DuplicateIds,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Document> {
+    let mut declarations: IndexMap<String, Vec<usize>> = IndexMap::new();
+    for (position, doc) in documents.iter().enumerate() {
+        declarations
+            .entry(doc.id.clone())
+            .or_default()
+            .push(position);
     }
 
-    Ok(graph)
+    let severity = match policy {
+        DuplicateIds::Error => Severity::Error,
+        DuplicateIds::Warn | DuplicateIds::KeepFirst => Severity::Warning,
+    };
+
+    let mut keep = vec![false; documents.len()];
+    for (id, positions) in declarations {
+        let winner = match policy {
+            DuplicateIds::KeepFirst => positions[0],
+            DuplicateIds::Warn | DuplicateIds::Error => positions[positions.len() - 1],
+        };
+        keep[winner] = true;
+
+        if positions.len() > 1 {
+            diagnostics.push(Diagnostic::new(
+                severity,
+                DiagnosticKind::DuplicateId { id },
+                positions
+                    .iter()
+                    .map(|&p| documents[p].file_path.clone())
+                    .collect(),
+            ));
+        }
+    }
+
+    documents
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(doc, keep)| keep.then_some(doc))
+        .collect()
 }
 
 #[cfg(test)]
@@ -190,7 +789,6 @@ mod tests {
     use super::*;
     use crate::parser::Document;
     use std::collections::HashMap;
-    use std::path::PathBuf;
 
     fn create_test_document(
         id: &str,
@@ -208,6 +806,31 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_set_page_urls() -> Result<()> {
+        let mut documents = vec![
+            create_test_document("intro", "Introduction", "Pre", vec![]),
+            create_test_document("lab", "Lab", "InClass", vec!["intro"]),
+        ];
+        documents[0].file_path = PathBuf::from("site/intro.qmd");
+        documents[1].file_path = PathBuf::from("site/week1/lab.Rmd");
+
+        let (mut graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+        assert_eq!(graph.graph[graph.node_map["intro"]].url, None);
+
+        graph.set_page_urls(Path::new("site"), &LinksConfig::default());
+        assert_eq!(
+            graph.graph[graph.node_map["intro"]].url.as_deref(),
+            Some("intro.html")
+        );
+        assert_eq!(
+            graph.graph[graph.node_map["lab"]].url.as_deref(),
+            Some("week1/lab.html")
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_build_simple_graph() -> Result<()> {
         let documents = vec![
@@ -215,7 +838,7 @@ mod tests {
             create_test_document("advanced", "Advanced Topics", "Post", vec!["intro"]),
         ];
 
-        let graph = build_graph(documents)?;
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
 
         assert_eq!(graph.node_count(), 2);
         assert_eq!(graph.edge_count(), 1);
@@ -238,7 +861,7 @@ mod tests {
             ),
         ];
 
-        let graph = build_graph(documents)?;
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
 
         assert_eq!(graph.node_count(), 4);
         assert_eq!(graph.edge_count(), 4);
@@ -268,11 +891,22 @@ mod tests {
             vec!["missing"],
         )];
 
-        let graph = build_graph(documents)?;
+        let (graph, diagnostics) = build_graph(documents, DuplicateIds::Warn)?;
 
         // Should still create the graph, but with a warning
         assert_eq!(graph.node_count(), 1);
         assert_eq!(graph.edge_count(), 0);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(
+            diagnostics[0].kind,
+            DiagnosticKind::MissingPrerequisite {
+                course: "advanced".to_string(),
+                prerequisite: "missing".to_string(),
+                edge: EdgeKind::Required,
+            }
+        );
+        assert_eq!(diagnostics[0].files, vec![PathBuf::from("advanced.qmd")]);
 
         Ok(())
     }
@@ -285,7 +919,7 @@ mod tests {
             create_test_document("advanced", "Advanced Topics", "Post", vec!["micro"]),
         ];
 
-        let graph = build_graph(documents)?;
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
         let sorted = graph.topological_sort()?;
 
         assert_eq!(sorted.len(), 3);
@@ -300,4 +934,391 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_cycle_diagnostic() -> Result<()> {
+        let documents = vec![
+            create_test_document("intro", "Introduction", "Pre", vec![]),
+            create_test_document("a", "A", "InClass", vec!["intro", "c"]),
+            create_test_document("b", "B", "InClass", vec!["a"]),
+            create_test_document("c", "C", "InClass", vec!["b"]),
+        ];
+
+        let (graph, diagnostics) = build_graph(documents, DuplicateIds::Warn)?;
+
+        assert!(graph.has_cycles());
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(
+            diagnostics[0].kind,
+            DiagnosticKind::Cycle {
+                path: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                members: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            }
+        );
+        assert!(diagnostics[0].message().contains("a -> b -> c -> a"));
+        assert_eq!(diagnostics[0].files.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_self_loop_diagnostic() -> Result<()> {
+        let documents = vec![create_test_document("loop", "Loop", "Pre", vec!["loop"])];
+
+        let (graph, diagnostics) = build_graph(documents, DuplicateIds::Warn)?;
+
+        assert_eq!(graph.edge_count(), 0);
+        assert!(!graph.has_cycles());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code(), "self-loop");
+        assert_eq!(diagnostics[0].ids(), vec!["loop"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_id_diagnostic() -> Result<()> {
+        let mut second = create_test_document("intro", "Intro Again", "Pre", vec![]);
+        second.file_path = PathBuf::from("other/intro.qmd");
+        let documents = vec![
+            create_test_document("intro", "Introduction", "Pre", vec![]),
+            second,
+        ];
+
+        let (graph, diagnostics) = build_graph(documents, DuplicateIds::Warn)?;
+
+        assert_eq!(graph.node_count(), 1);
+        assert_eq!(graph.get_node("intro").unwrap().title, "Intro Again");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code(), "duplicate-id");
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(
+            diagnostics[0].files,
+            vec![PathBuf::from("intro.qmd"), PathBuf::from("other/intro.qmd")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_id_policies() -> Result<()> {
+        let documents = || {
+            let mut second = create_test_document("intro", "Intro Again", "Pre", vec!["base"]);
+            second.file_path = PathBuf::from("other/intro.qmd");
+            vec![
+                create_test_document("base", "Base", "Pre", vec![]),
+                create_test_document("intro", "Introduction", "Pre", vec![]),
+                second,
+            ]
+        };
+
+        let (graph, diagnostics) = build_graph(documents(), DuplicateIds::KeepFirst)?;
+        assert_eq!(graph.get_node("intro").unwrap().title, "Introduction");
+        // The dropped document's prerequisites must not leak into the graph
+        assert_eq!(graph.edge_count(), 0);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+
+        let (graph, diagnostics) = build_graph(documents(), DuplicateIds::Error)?;
+        assert_eq!(graph.edge_count(), 1);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].files.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_edges() -> Result<()> {
+        let mut lecture = create_test_document("lecture", "Lecture", "InClass", vec!["intro"]);
+        lecture.recommended = vec!["reading".to_string(), "gone".to_string()];
+        lecture.corequisites = vec!["lab".to_string()];
+        let mut lab = create_test_document("lab", "Lab", "InClass", vec!["intro"]);
+        lab.corequisites = vec!["lecture".to_string()];
+        let documents = vec![
+            create_test_document("intro", "Introduction", "Pre", vec![]),
+            create_test_document("reading", "Reading", "Pre", vec![]),
+            lecture,
+            lab,
+        ];
+
+        let (graph, diagnostics) = build_graph(documents, DuplicateIds::Warn)?;
+
+        // Corequisites listed on both courses make a single edge
+        let kinds: Vec<(&str, &str, EdgeKind)> = graph
+            .typed_edges()
+            .map(|(s, t, kind)| (graph.graph[s].id.as_str(), graph.graph[t].id.as_str(), kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("intro", "lecture", EdgeKind::Required),
+                ("reading", "lecture", EdgeKind::Recommended),
+                ("lab", "lecture", EdgeKind::Corequisite),
+                ("intro", "lab", EdgeKind::Required),
+            ]
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message(),
+            "Recommended course 'gone' for course 'lecture' not found in documents"
+        );
+
+        // Analyses on a graph restricted to some kinds
+        let required = graph.with_edge_kinds(&[EdgeKind::Required]);
+        assert_eq!(required.node_count(), 4);
+        assert_eq!(required.edge_count(), 2);
+        assert_eq!(required.find_root_nodes().len(), 2);
+        assert_eq!(graph.find_root_nodes().len(), 2);
+        assert_eq!(graph.with_edge_kinds(&EdgeKind::ORDERING).edge_count(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_corequisites_are_not_cycles() -> Result<()> {
+        let mut lab = create_test_document("lab", "Lab", "InClass", vec!["lecture"]);
+        lab.corequisites = vec!["lecture".to_string()];
+        let mut lecture = create_test_document("lecture", "Lecture", "InClass", vec![]);
+        lecture.recommended = vec!["review".to_string()];
+        let documents = vec![
+            lab,
+            lecture,
+            create_test_document("review", "Review", "Post", vec!["lab"]),
+        ];
+
+        // lecture -> lab -> review is required, and review is recommended
+        // before lecture: the cycle is reported, the corequisite is not part
+        // of it
+        let (graph, diagnostics) = build_graph(documents, DuplicateIds::Warn)?;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code(), "cycle");
+        assert_eq!(diagnostics[0].ids(), vec!["lab", "lecture", "review"]);
+
+        assert!(graph.has_cycles());
+        assert!(!graph.with_edge_kinds(&[EdgeKind::Required]).has_cycles());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_corequisite_against_prerequisite_is_not_a_cycle() -> Result<()> {
+        // a lists b as a corequisite, stored as b -> a, while b requires a
+        let mut a = create_test_document("a", "A", "InClass", vec![]);
+        a.corequisites = vec!["b".to_string()];
+        let documents = vec![a, create_test_document("b", "B", "InClass", vec!["a"])];
+
+        let (graph, diagnostics) = build_graph(documents, DuplicateIds::Warn)?;
+        assert!(diagnostics.is_empty());
+        assert!(!graph.has_cycles());
+        assert!(graph.cyclic_components().is_empty());
+        assert!(graph.cycle_edges().is_empty());
+
+        let order: Vec<&str> = graph
+            .topological_sort()?
+            .into_iter()
+            .map(|idx| graph.graph[idx].id.as_str())
+            .collect();
+        assert_eq!(order, vec!["a", "b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transitive_reduction() -> Result<()> {
+        let mut advanced = create_test_document(
+            "advanced",
+            "Advanced Topics",
+            "Post",
+            vec!["intro", "micro"],
+        );
+        advanced.recommended = vec!["basics".to_string()];
+        advanced.corequisites = vec!["intro".to_string()];
+        let documents = vec![
+            create_test_document("basics", "Basics", "Pre", vec![]),
+            create_test_document("intro", "Introduction", "Pre", vec!["basics"]),
+            create_test_document("micro", "Microeconomics", "InClass", vec!["intro"]),
+            advanced,
+        ];
+
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+        let redundant: Vec<(&str, &str, &str)> = graph
+            .redundant_edges()
+            .iter()
+            .map(|r| {
+                (
+                    graph.graph[r.source].id.as_str(),
+                    graph.graph[r.target].id.as_str(),
+                    graph.graph[r.via].id.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            redundant,
+            vec![
+                ("intro", "advanced", "micro"),
+                ("basics", "advanced", "micro")
+            ]
+        );
+
+        let reduced = graph.transitive_reduction();
+        assert_eq!(reduced.node_count(), 4);
+        assert_eq!(reduced.edge_count(), graph.edge_count() - 2);
+        // The corequisite stays, as does every dependency
+        assert!(reduced
+            .typed_edges()
+            .any(|(_, _, kind)| kind == EdgeKind::Corequisite));
+        assert!(reduced.transitive_reduction().redundant_edges().is_empty());
+        let order = reduced.topological_sort()?;
+        assert_eq!(reduced.graph[order[0]].id, "basics");
+        assert_eq!(reduced.graph[order[3]].id, "advanced");
+
+        let (_, diagnostics) = reduce_graph(&graph);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].code(), "redundant-prerequisite");
+        assert_eq!(
+            diagnostics[0].message(),
+            "Course 'advanced' lists 'intro' as a prerequisite, which is already implied by 'micro'"
+        );
+        assert_eq!(diagnostics[0].files, vec![PathBuf::from("advanced.qmd")]);
+        assert!(diagnostics[1].message().contains("as a recommended course"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transitive_reduction_keeps_cycles_connected() -> Result<()> {
+        // b and c require each other; either would make a -> the other
+        // look redundant
+        let documents = vec![
+            create_test_document("a", "A", "Pre", vec![]),
+            create_test_document("b", "B", "Pre", vec!["a", "c"]),
+            create_test_document("c", "C", "Pre", vec!["a", "b"]),
+            create_test_document("d", "D", "Pre", vec!["a", "b"]),
+        ];
+
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+        let reduced = graph.transitive_reduction();
+
+        assert_eq!(reduced.edge_count(), graph.edge_count() - 1);
+        assert!(!reduced
+            .graph
+            .contains_edge(graph.node_map["a"], graph.node_map["d"]));
+        assert!(reduced
+            .graph
+            .contains_edge(graph.node_map["a"], graph.node_map["b"]));
+        assert!(reduced
+            .graph
+            .contains_edge(graph.node_map["a"], graph.node_map["c"]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transitive_reduction_keeps_edges_from_cycles() -> Result<()> {
+        // b and c require each other, so each of b -> d and c -> d would
+        // look implied by the other
+        let documents = vec![
+            create_test_document("b", "B", "Pre", vec!["c"]),
+            create_test_document("c", "C", "Pre", vec!["b"]),
+            create_test_document("d", "D", "Pre", vec!["b", "c"]),
+        ];
+
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+        assert!(graph.redundant_edges().is_empty());
+
+        let reduced = graph.transitive_reduction();
+        assert_eq!(reduced.edge_count(), graph.edge_count());
+        assert!(reduced
+            .graph
+            .contains_edge(graph.node_map["b"], graph.node_map["d"]));
+        assert!(reduced
+            .graph
+            .contains_edge(graph.node_map["c"], graph.node_map["d"]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_focus() -> Result<()> {
+        let mut electives = create_test_document("electives", "Electives", "Post", vec![]);
+        electives.recommended = vec!["advanced".to_string()];
+        let mut lab = create_test_document("lab", "Lab", "InClass", vec![]);
+        lab.corequisites = vec!["micro".to_string()];
+        let documents = vec![
+            create_test_document("basics", "Basics", "Pre", vec![]),
+            create_test_document("intro", "Introduction", "Pre", vec!["basics"]),
+            create_test_document("micro", "Microeconomics", "InClass", vec!["intro"]),
+            create_test_document("macro", "Macroeconomics", "InClass", vec!["intro"]),
+            create_test_document("advanced", "Advanced Topics", "Post", vec!["micro"]),
+            electives,
+            lab,
+        ];
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+        let ids = |graph: &CourseGraph| -> Vec<String> {
+            graph.nodes().map(|(_, node)| node.id.clone()).collect()
+        };
+
+        let upstream = graph.focus("micro", FocusDirection::Upstream, None)?;
+        assert_eq!(ids(&upstream), vec!["basics", "intro", "micro"]);
+        assert_eq!(upstream.edge_count(), 2);
+        assert!(upstream
+            .graph
+            .contains_edge(upstream.node_map["intro"], upstream.node_map["micro"]));
+
+        let downstream = graph.focus("micro", FocusDirection::Downstream, None)?;
+        assert_eq!(ids(&downstream), vec!["micro", "advanced", "electives"]);
+
+        let nearby = graph.focus("micro", FocusDirection::Both, Some(1))?;
+        assert_eq!(ids(&nearby), vec!["intro", "micro", "advanced"]);
+        assert_eq!(
+            ids(&graph.focus("micro", FocusDirection::Both, Some(0))?),
+            vec!["micro"]
+        );
+
+        // Corequisites do not lead anywhere
+        let lab = graph.focus("lab", FocusDirection::Both, None)?;
+        assert_eq!(ids(&lab), vec!["lab"]);
+
+        let error = graph
+            .focus("nowhere", FocusDirection::Both, None)
+            .unwrap_err();
+        assert!(error.to_string().contains("'nowhere'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_cycles() -> Result<()> {
+        let documents = vec![
+            create_test_document("a", "A", "Pre", vec!["d"]),
+            create_test_document("b", "B", "Pre", vec!["a"]),
+            create_test_document("c", "C", "Pre", vec!["b"]),
+            create_test_document("d", "D", "Pre", vec!["c", "b"]),
+            create_test_document("x", "X", "Pre", vec!["y"]),
+            create_test_document("y", "Y", "Pre", vec!["x"]),
+            create_test_document("free", "Free", "Pre", vec!["a"]),
+        ];
+
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn)?;
+
+        let components = graph.cyclic_components();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].len(), 4);
+
+        // Shortest cycle through `a` skips `c`
+        let cycles = graph.find_cycles();
+        assert_eq!(graph.format_cycle(&cycles[0]), "a -> b -> d -> a");
+        assert_eq!(graph.format_cycle(&cycles[1]), "x -> y -> x");
+
+        let cycle_edges = graph.cycle_edges();
+        assert_eq!(cycle_edges.len(), 7);
+        assert!(!cycle_edges.contains(&(graph.node_map["a"], graph.node_map["free"])));
+
+        let error = graph.topological_sort().unwrap_err().to_string();
+        assert!(error.contains("a -> b -> d -> a"));
+        assert!(error.contains("x -> y -> x"));
+
+        Ok(())
+    }
 }
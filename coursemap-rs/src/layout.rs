@@ -0,0 +1,690 @@
+//! Layered graph layout for rendering course maps without Graphviz
+//!
+//! This is a small Sugiyama-style layout: cycles are broken by reversing back
+//! edges, nodes are assigned to ranks by longest path, long edges are split
+//! with virtual nodes, crossings are reduced with barycenter sweeps, and
+//! horizontal positions are balanced against neighbouring ranks.
+
+use petgraph::graph::NodeIndex;
+
+use crate::graph::{CourseGraph, EdgeKind};
+
+const CHAR_WIDTH: f64 = 7.5;
+const LINE_HEIGHT: f64 = 18.0;
+const NODE_PADDING_X: f64 = 12.0;
+const NODE_PADDING_Y: f64 = 8.0;
+const NODE_SEPARATION: f64 = 30.0;
+const RANK_SEPARATION: f64 = 60.0;
+const MARGIN: f64 = 20.0;
+const ORDERING_SWEEPS: usize = 8;
+const POSITIONING_ROUNDS: usize = 4;
+
+/// Position and size of a node; `x` and `y` are the center of the box
+#[derive(Debug, Clone)]
+pub struct NodeBox {
+    pub index: NodeIndex,
+    pub rank: usize,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl NodeBox {
+    pub fn left(&self) -> f64 {
+        self.x - self.width / 2.0
+    }
+
+    pub fn top(&self) -> f64 {
+        self.y - self.height / 2.0
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height / 2.0
+    }
+}
+
+/// Route of an edge from its source node to its target node
+#[derive(Debug, Clone)]
+pub struct EdgePath {
+    pub source: NodeIndex,
+    pub target: NodeIndex,
+    pub kind: EdgeKind,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// A computed layout for a course graph
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub width: f64,
+    pub height: f64,
+    pub nodes: Vec<NodeBox>,
+    pub edges: Vec<EdgePath>,
+}
+
+/// Compute a top-to-bottom layered layout for the given graph
+pub fn compute_layout(graph: &CourseGraph) -> Layout {
+    let node_count = graph.node_count();
+    let sizes: Vec<(f64, f64)> = graph
+        .graph
+        .node_indices()
+        .map(|idx| label_size(&graph.graph[idx].display_name))
+        .collect();
+
+    // Self-loops carry no layout information
+    let (edges, kinds): (Vec<(usize, usize)>, Vec<EdgeKind>) = graph
+        .typed_edges()
+        .filter(|(source, target, _)| source != target)
+        .map(|(source, target, kind)| ((source.index(), target.index()), kind))
+        .unzip();
+
+    // Corequisites have no direction, so they do not rank courses; like
+    // `constraint=false` in DOT output, they are only routed
+    let ranked: Vec<usize> = (0..edges.len())
+        .filter(|&edge| kinds[edge] != EdgeKind::Corequisite)
+        .collect();
+    let ranked_edges: Vec<(usize, usize)> = ranked.iter().map(|&edge| edges[edge]).collect();
+
+    let reversed = find_back_edges(node_count, &ranked_edges);
+    let acyclic: Vec<(usize, usize)> = ranked_edges
+        .iter()
+        .zip(&reversed)
+        .map(|(&(s, t), &rev)| if rev { (t, s) } else { (s, t) })
+        .collect();
+
+    let ranks = assign_ranks(node_count, &acyclic);
+
+    // Split long edges into chains through virtual nodes, one per crossed rank
+    let mut vertex_rank = ranks.clone();
+    let mut chains: Vec<Vec<usize>> = Vec::with_capacity(acyclic.len());
+    for &(source, target) in &acyclic {
+        let mut chain = vec![source];
+        for rank in ranks[source] + 1..ranks[target] {
+            chain.push(vertex_rank.len());
+            vertex_rank.push(rank);
+        }
+        chain.push(target);
+        chains.push(chain);
+    }
+
+    let vertex_count = vertex_rank.len();
+    let mut upper: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+    let mut lower: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+    for chain in &chains {
+        for pair in chain.windows(2) {
+            lower[pair[0]].push(pair[1]);
+            upper[pair[1]].push(pair[0]);
+        }
+    }
+
+    let rank_count = vertex_rank.iter().max().map_or(0, |&max| max + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); rank_count];
+    for (vertex, &rank) in vertex_rank.iter().enumerate() {
+        layers[rank].push(vertex);
+    }
+
+    // Start from the navigation order where there is one; the sweeps sort
+    // stably, so it also settles ties between equally good positions
+    for layer in &mut layers {
+        layer.sort_by_key(|&v| {
+            let hint = (v < node_count).then(|| graph.graph[NodeIndex::new(v)].order);
+            (hint.flatten().unwrap_or(usize::MAX), v)
+        });
+    }
+
+    order_layers(&mut layers, &upper, &lower);
+
+    let widths: Vec<f64> = (0..vertex_count)
+        .map(|v| if v < node_count { sizes[v].0 } else { 0.0 })
+        .collect();
+    let xs = position_layers(&layers, &widths, &upper, &lower);
+
+    // Vertical placement: each rank is as tall as its tallest node
+    let mut rank_centers = Vec::with_capacity(rank_count);
+    let mut top = MARGIN;
+    for layer in &layers {
+        let height = layer
+            .iter()
+            .filter(|&&v| v < node_count)
+            .map(|&v| sizes[v].1)
+            .fold(0.0, f64::max);
+        rank_centers.push(top + height / 2.0);
+        top += height + RANK_SEPARATION;
+    }
+    let ys: Vec<f64> = vertex_rank.iter().map(|&rank| rank_centers[rank]).collect();
+
+    let nodes: Vec<NodeBox> = (0..node_count)
+        .map(|v| NodeBox {
+            index: NodeIndex::new(v),
+            rank: ranks[v],
+            x: xs[v],
+            y: ys[v],
+            width: sizes[v].0,
+            height: sizes[v].1,
+        })
+        .collect();
+
+    let mut routes: Vec<Option<(bool, &Vec<usize>)>> = vec![None; edges.len()];
+    for ((&edge, &rev), chain) in ranked.iter().zip(&reversed).zip(&chains) {
+        routes[edge] = Some((rev, chain));
+    }
+
+    let edge_paths = edges
+        .iter()
+        .zip(&kinds)
+        .zip(routes)
+        .map(|((&(source, target), &kind), route)| {
+            let Some((rev, chain)) = route else {
+                return EdgePath {
+                    source: NodeIndex::new(source),
+                    target: NodeIndex::new(target),
+                    kind,
+                    points: direct_route(&nodes[source], &nodes[target]),
+                };
+            };
+            let last = chain.len() - 1;
+            let mut points: Vec<(f64, f64)> = chain
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| {
+                    let y = if v >= node_count {
+                        ys[v]
+                    } else if i == 0 {
+                        nodes[v].bottom()
+                    } else if i == last {
+                        nodes[v].top()
+                    } else {
+                        ys[v]
+                    };
+                    (xs[v], y)
+                })
+                .collect();
+            if rev {
+                points.reverse();
+            }
+            EdgePath {
+                source: NodeIndex::new(source),
+                target: NodeIndex::new(target),
+                kind,
+                points,
+            }
+        })
+        .collect();
+
+    let width = nodes
+        .iter()
+        .map(|node| node.x + node.width / 2.0)
+        .chain(xs.iter().copied())
+        .fold(0.0, f64::max)
+        + MARGIN;
+    let height = if rank_count == 0 {
+        2.0 * MARGIN
+    } else {
+        top - RANK_SEPARATION + MARGIN
+    };
+
+    Layout {
+        width: width.max(2.0 * MARGIN),
+        height,
+        nodes,
+        edges: edge_paths,
+    }
+}
+
+/// A straight line between two nodes: side to side within a rank,
+/// otherwise from the bottom of the upper node to the top of the lower one
+fn direct_route(source: &NodeBox, target: &NodeBox) -> Vec<(f64, f64)> {
+    if source.rank == target.rank {
+        let direction = if source.x <= target.x { 1.0 } else { -1.0 };
+        vec![
+            (source.x + direction * source.width / 2.0, source.y),
+            (target.x - direction * target.width / 2.0, target.y),
+        ]
+    } else if source.rank < target.rank {
+        vec![(source.x, source.bottom()), (target.x, target.top())]
+    } else {
+        vec![(source.x, source.top()), (target.x, target.bottom())]
+    }
+}
+
+/// Estimate the box size needed for a (possibly multi-line) label
+fn label_size(label: &str) -> (f64, f64) {
+    let lines: Vec<&str> = label.lines().collect();
+    let longest = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let line_count = lines.len().max(1);
+
+    (
+        longest as f64 * CHAR_WIDTH + 2.0 * NODE_PADDING_X,
+        line_count as f64 * LINE_HEIGHT + 2.0 * NODE_PADDING_Y,
+    )
+}
+
+/// Find edges that close a cycle during a depth-first search
+///
+/// Reversing the returned edges makes the graph acyclic.
+fn find_back_edges(node_count: usize, edges: &[(usize, usize)]) -> Vec<bool> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        Active,
+        Done,
+    }
+
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+    for (edge, &(source, _)) in edges.iter().enumerate() {
+        outgoing[source].push(edge);
+    }
+
+    let mut reversed = vec![false; edges.len()];
+    let mut state = vec![State::Unvisited; node_count];
+
+    for root in 0..node_count {
+        if state[root] != State::Unvisited {
+            continue;
+        }
+        state[root] = State::Active;
+        let mut stack = vec![(root, 0)];
+
+        while let Some(&mut (node, ref mut next)) = stack.last_mut() {
+            if let Some(&edge) = outgoing[node].get(*next) {
+                *next += 1;
+                let target = edges[edge].1;
+                match state[target] {
+                    State::Unvisited => {
+                        state[target] = State::Active;
+                        stack.push((target, 0));
+                    }
+                    State::Active => reversed[edge] = true,
+                    State::Done => {}
+                }
+            } else {
+                state[node] = State::Done;
+                stack.pop();
+            }
+        }
+    }
+
+    reversed
+}
+
+/// Assign ranks by longest path, then pull source nodes down next to their
+/// earliest dependent so that entry points don't float at the top
+fn assign_ranks(node_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+    let mut in_degree = vec![0usize; node_count];
+    for &(source, target) in edges {
+        outgoing[source].push(target);
+        in_degree[target] += 1;
+    }
+
+    let mut ranks = vec![0usize; node_count];
+    let mut remaining = in_degree.clone();
+    let mut queue: Vec<usize> = (0..node_count).filter(|&v| remaining[v] == 0).collect();
+    let mut head = 0;
+    while head < queue.len() {
+        let node = queue[head];
+        head += 1;
+        for &target in &outgoing[node] {
+            ranks[target] = ranks[target].max(ranks[node] + 1);
+            remaining[target] -= 1;
+            if remaining[target] == 0 {
+                queue.push(target);
+            }
+        }
+    }
+
+    for node in 0..node_count {
+        if in_degree[node] == 0 {
+            if let Some(min_rank) = outgoing[node].iter().map(|&t| ranks[t]).min() {
+                ranks[node] = min_rank - 1;
+            }
+        }
+    }
+
+    ranks
+}
+
+/// Reorder each layer to reduce edge crossings, keeping the best ordering seen
+fn order_layers(layers: &mut [Vec<usize>], upper: &[Vec<usize>], lower: &[Vec<usize>]) {
+    if layers.len() < 2 {
+        return;
+    }
+
+    let mut best = layers.to_vec();
+    let mut best_crossings = count_crossings(layers, lower);
+
+    for sweep in 0..ORDERING_SWEEPS {
+        if sweep % 2 == 0 {
+            for rank in 1..layers.len() {
+                let (fixed, free) = layers.split_at_mut(rank);
+                reorder_by_barycenter(&mut free[0], &fixed[rank - 1], upper);
+            }
+        } else {
+            for rank in (0..layers.len() - 1).rev() {
+                let (free, fixed) = layers.split_at_mut(rank + 1);
+                reorder_by_barycenter(&mut free[rank], &fixed[0], lower);
+            }
+        }
+
+        let crossings = count_crossings(layers, lower);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best = layers.to_vec();
+        }
+    }
+
+    layers.clone_from_slice(&best);
+}
+
+fn reorder_by_barycenter(layer: &mut [usize], fixed: &[usize], neighbors: &[Vec<usize>]) {
+    let mut position = vec![usize::MAX; neighbors.len()];
+    for (i, &v) in fixed.iter().enumerate() {
+        position[v] = i;
+    }
+
+    let mut keyed: Vec<(f64, usize)> = layer
+        .iter()
+        .enumerate()
+        .map(|(current, &v)| {
+            let adjacent: Vec<usize> = neighbors[v].iter().map(|&u| position[u]).collect();
+            let key = if adjacent.is_empty() {
+                current as f64
+            } else {
+                adjacent.iter().sum::<usize>() as f64 / adjacent.len() as f64
+            };
+            (key, v)
+        })
+        .collect();
+
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for (slot, (_, v)) in layer.iter_mut().zip(keyed) {
+        *slot = v;
+    }
+}
+
+fn count_crossings(layers: &[Vec<usize>], lower: &[Vec<usize>]) -> usize {
+    let mut position = vec![0usize; lower.len()];
+    for layer in layers {
+        for (i, &v) in layer.iter().enumerate() {
+            position[v] = i;
+        }
+    }
+
+    layers
+        .iter()
+        .map(|layer| {
+            let segments: Vec<(usize, usize)> = layer
+                .iter()
+                .flat_map(|&v| lower[v].iter().map(move |&w| (v, w)))
+                .map(|(v, w)| (position[v], position[w]))
+                .collect();
+
+            let mut crossings = 0;
+            for (i, a) in segments.iter().enumerate() {
+                for b in &segments[i + 1..] {
+                    if (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1) {
+                        crossings += 1;
+                    }
+                }
+            }
+            crossings
+        })
+        .sum()
+}
+
+/// Assign horizontal centers, pulling nodes toward their neighbours while
+/// keeping the order within each layer and a minimum separation
+fn position_layers(
+    layers: &[Vec<usize>],
+    widths: &[f64],
+    upper: &[Vec<usize>],
+    lower: &[Vec<usize>],
+) -> Vec<f64> {
+    let mut xs = vec![0.0; widths.len()];
+
+    for layer in layers {
+        let mut left = 0.0;
+        for &v in layer {
+            xs[v] = left + widths[v] / 2.0;
+            left += widths[v] + NODE_SEPARATION;
+        }
+    }
+
+    for round in 0..POSITIONING_ROUNDS {
+        let downward = round % 2 == 0;
+        let (neighbors, order): (&[Vec<usize>], Vec<usize>) = if downward {
+            (upper, (0..layers.len()).collect())
+        } else {
+            (lower, (0..layers.len()).rev().collect())
+        };
+
+        for rank in order {
+            let layer = &layers[rank];
+            let desired: Vec<f64> = layer
+                .iter()
+                .map(|&v| {
+                    if neighbors[v].is_empty() {
+                        xs[v]
+                    } else {
+                        neighbors[v].iter().map(|&u| xs[u]).sum::<f64>() / neighbors[v].len() as f64
+                    }
+                })
+                .collect();
+
+            for (&v, x) in layer.iter().zip(separate(layer, &desired, widths)) {
+                xs[v] = x;
+            }
+        }
+    }
+
+    // Shift everything so the leftmost box starts at the margin
+    let min_left = layers
+        .iter()
+        .flatten()
+        .map(|&v| xs[v] - widths[v] / 2.0)
+        .fold(f64::INFINITY, f64::min);
+    if min_left.is_finite() {
+        for x in &mut xs {
+            *x += MARGIN - min_left;
+        }
+    }
+
+    xs
+}
+
+/// Move desired positions apart just enough to respect node separation
+///
+/// Averages a left-to-right and a right-to-left resolution; both satisfy the
+/// separation constraints, so their mean does too.
+fn separate(layer: &[usize], desired: &[f64], widths: &[f64]) -> Vec<f64> {
+    let gap = |a: usize, b: usize| (widths[layer[a]] + widths[layer[b]]) / 2.0 + NODE_SEPARATION;
+
+    let mut forward = desired.to_vec();
+    for i in 1..layer.len() {
+        forward[i] = forward[i].max(forward[i - 1] + gap(i - 1, i));
+    }
+
+    let mut backward = desired.to_vec();
+    for i in (0..layer.len().saturating_sub(1)).rev() {
+        backward[i] = backward[i].min(backward[i + 1] - gap(i, i + 1));
+    }
+
+    forward
+        .iter()
+        .zip(&backward)
+        .map(|(f, b)| (f + b) / 2.0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DuplicateIds;
+    use crate::graph::build_graph;
+    use crate::parser::Document;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn doc(id: &str, prerequisites: Vec<&str>) -> Document {
+        Document::new(
+            id.to_string(),
+            id.to_string(),
+            PathBuf::from(format!("{id}.qmd")),
+            "Pre".to_string(),
+            prerequisites.into_iter().map(|s| s.to_string()).collect(),
+            HashMap::new(),
+        )
+    }
+
+    fn node<'a>(layout: &'a Layout, graph: &CourseGraph, id: &str) -> &'a NodeBox {
+        let index = graph.node_map[id];
+        layout.nodes.iter().find(|n| n.index == index).unwrap()
+    }
+
+    #[test]
+    fn test_ranks_follow_prerequisites() {
+        let (graph, _) = build_graph(
+            vec![
+                doc("intro", vec![]),
+                doc("micro", vec!["intro"]),
+                doc("macro", vec!["intro"]),
+                doc("advanced", vec!["micro", "macro"]),
+            ],
+            DuplicateIds::Warn,
+        )
+        .unwrap();
+        let layout = compute_layout(&graph);
+
+        assert_eq!(node(&layout, &graph, "intro").rank, 0);
+        assert_eq!(node(&layout, &graph, "micro").rank, 1);
+        assert_eq!(node(&layout, &graph, "macro").rank, 1);
+        assert_eq!(node(&layout, &graph, "advanced").rank, 2);
+        assert!(node(&layout, &graph, "intro").y < node(&layout, &graph, "micro").y);
+        assert_eq!(layout.edges.len(), 4);
+    }
+
+    #[test]
+    fn test_corequisites_do_not_rank() {
+        let mut lab = doc("lab", vec!["intro"]);
+        lab.corequisites = vec!["lecture".to_string()];
+        let (graph, _) = build_graph(
+            vec![
+                doc("intro", vec![]),
+                doc("lecture", vec!["intro"]),
+                lab,
+                doc("pair", vec![]),
+                Document {
+                    corequisites: vec!["pair".to_string()],
+                    ..doc("partner", vec![])
+                },
+            ],
+            DuplicateIds::Warn,
+        )
+        .unwrap();
+        let layout = compute_layout(&graph);
+
+        assert_eq!(node(&layout, &graph, "lecture").rank, 1);
+        assert_eq!(node(&layout, &graph, "lab").rank, 1);
+        assert_eq!(node(&layout, &graph, "pair").rank, 0);
+        assert_eq!(node(&layout, &graph, "partner").rank, 0);
+
+        // Routed side to side, between the two boxes
+        let lab_edge = layout
+            .edges
+            .iter()
+            .find(|edge| {
+                edge.kind == EdgeKind::Corequisite && edge.source == graph.node_map["lecture"]
+            })
+            .unwrap();
+        assert_eq!(lab_edge.points.len(), 2);
+        assert_eq!(lab_edge.points[0].1, lab_edge.points[1].1);
+    }
+
+    #[test]
+    fn test_nodes_in_same_rank_do_not_overlap() {
+        let (graph, _) = build_graph(
+            vec![
+                doc("intro", vec![]),
+                doc("a", vec!["intro"]),
+                doc("b", vec!["intro"]),
+                doc("c", vec!["intro"]),
+            ],
+            DuplicateIds::Warn,
+        )
+        .unwrap();
+        let layout = compute_layout(&graph);
+
+        let mut row: Vec<&NodeBox> = layout.nodes.iter().filter(|n| n.rank == 1).collect();
+        row.sort_by(|a, b| a.x.total_cmp(&b.x));
+        for pair in row.windows(2) {
+            assert!(pair[0].x + pair[0].width / 2.0 <= pair[1].left());
+        }
+        assert!(layout.nodes.iter().all(|n| n.left() >= 0.0));
+    }
+
+    #[test]
+    fn test_long_edges_route_through_intermediate_ranks() {
+        let (graph, _) = build_graph(
+            vec![
+                doc("intro", vec![]),
+                doc("micro", vec!["intro"]),
+                doc("advanced", vec!["micro", "intro"]),
+            ],
+            DuplicateIds::Warn,
+        )
+        .unwrap();
+        let layout = compute_layout(&graph);
+
+        let long_edge = layout
+            .edges
+            .iter()
+            .find(|e| e.source == graph.node_map["intro"] && e.target == graph.node_map["advanced"])
+            .unwrap();
+        assert_eq!(long_edge.points.len(), 3);
+    }
+
+    #[test]
+    fn test_cycles_are_laid_out() {
+        let (graph, _) = build_graph(
+            vec![doc("a", vec!["b"]), doc("b", vec!["a"])],
+            DuplicateIds::Warn,
+        )
+        .unwrap();
+        let layout = compute_layout(&graph);
+
+        assert_eq!(layout.nodes.len(), 2);
+        assert_eq!(layout.edges.len(), 2);
+        assert_ne!(layout.nodes[0].rank, layout.nodes[1].rank);
+    }
+
+    #[test]
+    fn test_navigation_order_hint() {
+        let documents = ["c", "b", "a"]
+            .into_iter()
+            .enumerate()
+            .map(|(position, id)| Document {
+                order: Some(2 - position),
+                ..doc(id, vec![])
+            })
+            .chain([doc("unlisted", vec![])])
+            .collect();
+        let (graph, _) = build_graph(documents, DuplicateIds::Warn).unwrap();
+        let layout = compute_layout(&graph);
+
+        let x = |id: &str| node(&layout, &graph, id).x;
+        assert!(x("a") < x("b"));
+        assert!(x("b") < x("c"));
+        assert!(x("c") < x("unlisted"));
+    }
+
+    #[test]
+    fn test_empty_graph() {
+        let layout = compute_layout(&CourseGraph::new());
+        assert!(layout.nodes.is_empty());
+        assert!(layout.width > 0.0 && layout.height > 0.0);
+    }
+}
@@ -65,15 +65,25 @@
 //! ---
 //! ```
 
+pub mod cache;
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod config;
+pub mod diagnostics;
+pub mod extractors;
+pub mod filter;
+pub mod frontmatter;
 pub mod graph;
+pub mod layout;
 pub mod parser;
+pub mod quarto;
 pub mod renderer;
+pub mod roots;
+pub mod watch;
 
 pub use anyhow::{Error, Result};
 pub use config::Config;
+pub use diagnostics::{Diagnostic, DiagnosticKind, Severity};
 
 /// Common helper function to load config from optional path
 /// Used by language bindings to avoid code duplication
@@ -88,12 +98,17 @@ pub fn load_config_from_path(config_path: Option<&str>) -> Result<Config> {
 /// The main application structure
 pub struct App {
     pub config: config::Config,
+    /// Frontmatter extractor for each document extension
+    pub extractors: extractors::Extractors,
 }
 
 impl App {
     /// Create a new App instance with the given configuration
     pub fn new(config: config::Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            extractors: extractors::Extractors::default(),
+        }
     }
 
     /// Create a new App instance with config loaded from optional path
@@ -104,28 +119,126 @@ impl App {
     }
 
     /// Run the course map generation process
-    pub fn run(&self, input_dir: &str, output_path: &str, format: &str) -> Result<()> {
-        // Parse all documents in the input directory
-        let documents = parser::parse_directory(input_dir, &self.config)?;
+    ///
+    /// Returns the diagnostics found while parsing documents and building the
+    /// graph; they are not fatal, so the map is rendered regardless. Files that
+    /// fail to parse are left out of the map unless `config.strict` is set, in
+    /// which case the first failure is returned as an error. With
+    /// `config.reduce`, prerequisites implied by other prerequisites are left
+    /// out of the map and reported. `config.filter` and `config.focus` narrow
+    /// the map down to some of the courses.
+    pub fn run(&self, input_dir: &str, output_path: &str, format: &str) -> Result<Vec<Diagnostic>> {
+        self.run_roots(
+            &[roots::CourseRoot::unqualified(input_dir)],
+            output_path,
+            format,
+        )
+    }
 
-        // Build the dependency graph
-        let graph = graph::build_graph(documents)?;
+    /// Run the course map generation process for several course directories
+    ///
+    /// The map has one cluster per namespaced root, and lessons may depend on
+    /// lessons of other courses through qualified ids (`stats101:regression`).
+    pub fn run_roots(
+        &self,
+        roots: &[roots::CourseRoot],
+        output_path: &str,
+        format: &str,
+    ) -> Result<Vec<Diagnostic>> {
+        let (graph, diagnostics) = self.build(roots)?;
 
         // Render the graph to the specified format
         renderer::render_graph(&graph, output_path, format, &self.config)?;
 
-        Ok(())
+        Ok(diagnostics)
     }
 
     /// Generate DOT content as a string (for R/Python bindings)
     pub fn generate_dot_string(&self, input_dir: &str) -> Result<String> {
-        // Parse all documents in the input directory
-        let documents = parser::parse_directory(input_dir, &self.config)?;
-
-        // Build the dependency graph
-        let graph = graph::build_graph(documents)?;
+        let (graph, _) = self.build(&[roots::CourseRoot::unqualified(input_dir)])?;
 
         // Generate DOT content
         renderer::generate_dot_content(&graph, &self.config)
     }
+
+    /// Validate the documents in a directory without rendering
+    ///
+    /// In addition to the graph diagnostics returned by [`App::run`], this
+    /// reports unknown phases and documents without course map metadata.
+    /// Like [`App::run`], it reports redundant prerequisites when
+    /// `config.reduce` is set.
+    pub fn diagnostics(&self, input_dir: &str) -> Result<Vec<Diagnostic>> {
+        self.diagnostics_roots(&[roots::CourseRoot::unqualified(input_dir)])
+    }
+
+    /// Validate the documents of several course directories without rendering
+    pub fn diagnostics_roots(&self, roots: &[roots::CourseRoot]) -> Result<Vec<Diagnostic>> {
+        let report = roots::parse_roots(roots, &self.config, &self.extractors)?;
+        let mut diagnostics = report.diagnostics();
+        diagnostics.extend(diagnostics::check_documents(
+            &report.documents,
+            &self.config,
+        ));
+
+        let (graph, graph_diagnostics) =
+            graph::build_graph(report.documents, self.config.duplicate_ids)?;
+        diagnostics.extend(graph_diagnostics);
+        if self.config.reduce {
+            diagnostics.extend(graph::reduce_graph(&graph).1);
+        }
+
+        Ok(diagnostics)
+    }
+
+    /// Parse the input directories and build the dependency graph
+    ///
+    /// Fails if duplicate ids are found and the configuration treats them as
+    /// errors, or if the focused course does not exist.
+    fn build(&self, roots: &[roots::CourseRoot]) -> Result<(graph::CourseGraph, Vec<Diagnostic>)> {
+        // Parse all documents in the input directories
+        let report = roots::parse_roots(roots, &self.config, &self.extractors)?;
+        let mut diagnostics = report.diagnostics();
+
+        // Build the dependency graph
+        let (mut graph, graph_diagnostics) =
+            graph::build_graph(report.documents, self.config.duplicate_ids)?;
+        diagnostics.extend(graph_diagnostics);
+        if self.config.reduce {
+            let (reduced, redundant) = graph::reduce_graph(&graph);
+            graph = reduced;
+            diagnostics.extend(redundant);
+        }
+        let root = roots::common_dir(roots);
+        let links = self.config.links.clone().unwrap_or_default();
+        graph.set_page_urls(&root, &links);
+
+        if let Some(duplicate) = diagnostics
+            .iter()
+            .find(|d| d.is_error() && matches!(d.kind, DiagnosticKind::DuplicateId { .. }))
+        {
+            let files: Vec<String> = duplicate
+                .files
+                .iter()
+                .map(|f| f.display().to_string())
+                .collect();
+            return Err(anyhow::anyhow!(
+                "{}: {}",
+                duplicate.message(),
+                files.join(", ")
+            ));
+        }
+
+        if !self.config.filter.is_empty() {
+            graph = filter::filter_graph(&graph, &self.config.filter, &self.config.root_key, &root);
+            if self.config.reduce {
+                // Joining courses around the dropped ones can imply edges again
+                graph = graph.transitive_reduction();
+            }
+        }
+        if let Some(focus) = &self.config.focus {
+            graph = graph.focus(&focus.id, focus.direction, focus.depth)?;
+        }
+
+        Ok((graph, diagnostics))
+    }
 }
@@ -5,8 +5,16 @@ use anyhow::Result;
 #[cfg(feature = "cli")]
 use coursemap::{
     cli::{Cli, Commands},
-    renderer, App, Config,
+    config::{Engine, InputMode, CONFIG_FILE_NAMES},
+    renderer,
+    roots::CourseRoot,
+    watch::Watcher,
+    App, Config, Diagnostic,
 };
+#[cfg(feature = "cli")]
+use std::path::PathBuf;
+#[cfg(feature = "cli")]
+use std::time::Duration;
 
 #[cfg(feature = "cli")]
 fn main() -> Result<()> {
@@ -16,13 +24,31 @@ fn main() -> Result<()> {
         Some(Commands::ShowConfig { config }) => {
             show_config(config.as_ref())?;
         }
+        Some(Commands::Check {
+            input,
+            config,
+            input_mode,
+            deny_warnings,
+            strict,
+            reduce,
+        }) => {
+            run_cli_check(
+                &CourseRoot::from_specs(input)?,
+                config.as_ref(),
+                *input_mode,
+                *deny_warnings,
+                *strict,
+                *reduce,
+            )?;
+        }
         None => {
             // Default behavior: generate course map
-            if let Some(input_dir) = args.input_dir() {
-                run_cli_generate(&args, input_dir)?;
+            if let Some(roots) = args.input_roots()? {
+                run_cli_generate(&args, &roots)?;
             } else {
                 eprintln!("Error: Input directory is required");
                 eprintln!("Usage: coursemap <INPUT> [OPTIONS]");
+                eprintln!("       coursemap check <INPUT> [OPTIONS]");
                 eprintln!("       coursemap show-config [OPTIONS]");
                 eprintln!();
                 eprintln!("For more information, try '--help'.");
@@ -52,6 +78,59 @@ fn show_config(config_path: Option<&std::path::PathBuf>) -> Result<()> {
     for pattern in &config.ignore {
         println!("    {pattern}");
     }
+    if !config.include.is_empty() {
+        println!("  Include patterns:");
+        for pattern in &config.include {
+            println!("    {pattern}");
+        }
+    }
+    println!("  Extensions: {}", config.extensions.join(", "));
+    println!("  Engine: {}", config.engine);
+    println!("  Input mode: {}", config.input_mode);
+    println!("  Duplicate ids: {}", config.duplicate_ids);
+    println!("  Cache: {}", if config.cache { "on" } else { "off" });
+    println!("  Reduce: {}", if config.reduce { "on" } else { "off" });
+    if !config.filter.is_empty() {
+        let filter = &config.filter;
+        let paths = |paths: &[PathBuf]| {
+            paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        println!("  Filter:");
+        if !filter.phases.is_empty() {
+            println!("    Phases: {}", filter.phases.join(", "));
+        }
+        if !filter.exclude_phases.is_empty() {
+            println!("    Excluded phases: {}", filter.exclude_phases.join(", "));
+        }
+        for predicate in &filter.metadata {
+            println!("    Metadata: {predicate}");
+        }
+        if !filter.paths.is_empty() {
+            println!("    Paths: {}", paths(&filter.paths));
+        }
+        if !filter.exclude_paths.is_empty() {
+            println!("    Excluded paths: {}", paths(&filter.exclude_paths));
+        }
+    }
+    if let Some(focus) = &config.focus {
+        match focus.depth {
+            Some(depth) => println!("  Focus: {} ({}, depth {depth})", focus.id, focus.direction),
+            None => println!("  Focus: {} ({})", focus.id, focus.direction),
+        }
+    }
+    if let Some(links) = &config.links {
+        println!("  Links:");
+        if !links.base_url.is_empty() {
+            println!("    Base URL: {}", links.base_url);
+        }
+        for (source, rendered) in &links.extension_map {
+            println!("    .{source} -> .{rendered}");
+        }
+    }
 
     if let Some(config_path) = config_path {
         println!("  Configuration file: {}", config_path.display());
@@ -63,7 +142,54 @@ fn show_config(config_path: Option<&std::path::PathBuf>) -> Result<()> {
 }
 
 #[cfg(feature = "cli")]
-fn run_cli_generate(args: &Cli, input_dir: &str) -> Result<()> {
+fn run_cli_check(
+    roots: &[CourseRoot],
+    config_path: Option<&std::path::PathBuf>,
+    input_mode: Option<InputMode>,
+    deny_warnings: bool,
+    strict: bool,
+    reduce: bool,
+) -> Result<()> {
+    let mut config = if let Some(config_path) = config_path {
+        Config::from_file(config_path)?
+    } else {
+        Config::load_default()?
+    };
+    if let Some(input_mode) = input_mode {
+        config.input_mode = input_mode;
+    }
+    config.strict |= strict;
+    config.reduce |= reduce;
+
+    let input_dir = describe_roots(roots);
+    let diagnostics = match App::new(config).diagnostics_roots(roots) {
+        Ok(diagnostics) => diagnostics,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            std::process::exit(2);
+        }
+    };
+
+    print_diagnostics(&diagnostics);
+
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+
+    if diagnostics.is_empty() {
+        println!("No problems found in {input_dir}");
+    } else {
+        println!("Found {errors} error(s) and {warnings} warning(s) in {input_dir}");
+    }
+
+    if errors > 0 || (deny_warnings && warnings > 0) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "cli")]
+fn run_cli_generate(args: &Cli, roots: &[CourseRoot]) -> Result<()> {
     // Set up logging based on verbosity
     if args.verbose {
         env_logger::Builder::from_default_env()
@@ -72,66 +198,91 @@ fn run_cli_generate(args: &Cli, input_dir: &str) -> Result<()> {
     }
 
     // Load configuration
-    let config = if let Some(config_path) = &args.config {
-        Config::from_file(config_path)?
-    } else {
-        Config::load_default()?
-    };
+    let config = load_cli_config(args)?;
 
     if args.verbose {
         println!("Loaded configuration:");
         println!("  Root key: {}", config.root_key);
         println!("  Phases: {:?}", config.phase.keys().collect::<Vec<_>>());
         println!("  Ignore patterns: {:?}", config.ignore);
+        println!("  Include patterns: {:?}", config.include);
+        println!("  Engine: {}", config.engine);
+        println!("  Input mode: {}", config.input_mode);
         println!();
     }
 
+    let format = args.format_str();
+    let engine = config.engine;
+
     // Create and run the application
     let app = App::new(config);
 
-    println!("Scanning directory: {input_dir}");
+    for root in roots {
+        match &root.namespace {
+            Some(namespace) => {
+                println!(
+                    "Scanning directory: {} (as {namespace})",
+                    root.dir.display()
+                )
+            }
+            None => println!("Scanning directory: {}", root.dir.display()),
+        }
+    }
     println!("Output file: {}", args.output_path());
-    println!("Format: {}", args.format_str());
+    println!("Format: {format}");
     println!();
 
-    // Check if Graphviz is available for non-DOT formats
-    if args.format_str() != "dot" {
-        if !renderer::graphviz_available() {
-            eprintln!("Warning: Graphviz not found. Only DOT format will be available.");
-            eprintln!("To generate SVG/PNG files, please install Graphviz:");
-            eprintln!("  macOS: brew install graphviz");
-            eprintln!("  Ubuntu/Debian: sudo apt-get install graphviz");
-            eprintln!("  Windows: Download from https://graphviz.org/download/");
-            eprintln!();
-
-            if args.format_str() != "dot" {
-                return Err(anyhow::anyhow!(
-                    "Cannot generate {} format without Graphviz",
-                    args.format_str()
-                ));
-            }
-        } else if args.verbose {
-            if let Ok(info) = renderer::graphviz_info() {
-                println!("Graphviz found: {info}");
-                println!();
-            }
+    // Graphviz is needed for PNG, and for SVG unless the native engine can be used
+    let needs_graphviz = format == "png" || (format == "svg" && engine == Engine::Graphviz);
+    if needs_graphviz && !renderer::graphviz_available() {
+        eprintln!("Warning: Graphviz not found.");
+        eprintln!(
+            "To generate {} files with Graphviz, please install it:",
+            format.to_uppercase()
+        );
+        eprintln!("  macOS: brew install graphviz");
+        eprintln!("  Ubuntu/Debian: sudo apt-get install graphviz");
+        eprintln!("  Windows: Download from https://graphviz.org/download/");
+        if format == "svg" {
+            eprintln!("Or use --engine native to render SVG without Graphviz.");
+        }
+        eprintln!();
+
+        return Err(anyhow::anyhow!(
+            "Cannot generate {} format without Graphviz",
+            format
+        ));
+    }
+
+    if args.verbose && format == "svg" {
+        if engine == Engine::Native || (engine == Engine::Auto && !renderer::graphviz_available()) {
+            println!("Using the native SVG renderer");
+            println!();
+        } else if let Ok(info) = renderer::graphviz_info() {
+            println!("Graphviz found: {info}");
+            println!();
         }
     }
 
+    if args.watch {
+        return run_cli_watch(args, roots, app);
+    }
+
     // Run the application
-    match app.run(input_dir, args.output_path(), &args.format_str()) {
-        Ok(()) => {
+    match app.run_roots(roots, args.output_path(), &format) {
+        Ok(diagnostics) => {
+            print_diagnostics(&diagnostics);
             println!("Course map generated successfully!");
             Ok(())
         }
         Err(e) => {
-            eprintln!("Error: {e}");
+            eprintln!("Error: {e:#}");
 
             // Print additional context for common errors
             if e.to_string().contains("Directory does not exist") {
                 eprintln!("Make sure the input directory exists and contains course documents.");
             } else if e.to_string().contains("Graphviz") {
-                eprintln!("Try using --format dot to generate DOT format without Graphviz.");
+                eprintln!("Try using --engine native for SVG, or --format dot, to avoid Graphviz.");
             }
 
             std::process::exit(1);
@@ -139,6 +290,124 @@ fn run_cli_generate(args: &Cli, input_dir: &str) -> Result<()> {
     }
 }
 
+#[cfg(feature = "cli")]
+fn load_cli_config(args: &Cli) -> Result<Config> {
+    let mut config = if let Some(config_path) = &args.config {
+        Config::from_file(config_path)?
+    } else {
+        Config::load_default()?
+    };
+
+    if let Some(engine) = args.engine {
+        config.engine = engine;
+    }
+    if let Some(input_mode) = args.input_mode {
+        config.input_mode = input_mode;
+    }
+    config.strict |= args.strict;
+    config.cache |= args.cache;
+    config.reduce |= args.reduce;
+    config.filter.phases.extend(args.phase.iter().cloned());
+    config
+        .filter
+        .exclude_phases
+        .extend(args.exclude_phase.iter().cloned());
+    config.filter.metadata.extend(args.metadata.iter().cloned());
+    config.filter.paths.extend(args.path.iter().cloned());
+    config
+        .filter
+        .exclude_paths
+        .extend(args.exclude_path.iter().cloned());
+    if let Some(focus) = args.focus() {
+        config.focus = Some(focus);
+    }
+
+    Ok(config)
+}
+
+#[cfg(feature = "cli")]
+fn run_cli_watch(args: &Cli, roots: &[CourseRoot], mut app: App) -> Result<()> {
+    // Watch the configuration file in use, or every default location so
+    // that creating one is picked up as well
+    let config_files: Vec<PathBuf> = match &args.config {
+        Some(config_path) => vec![config_path.clone()],
+        None => CONFIG_FILE_NAMES.iter().map(PathBuf::from).collect(),
+    };
+    // The map itself is left out, or writing it would trigger a rebuild
+    let mut watcher = Watcher::new(
+        roots.iter().map(|root| root.dir.clone()).collect(),
+        &app.config,
+        config_files.clone(),
+        &[PathBuf::from(args.output_path())],
+        Duration::from_millis(args.poll_interval),
+        Duration::from_millis(args.debounce),
+    )?;
+
+    regenerate(&app, args, roots);
+
+    loop {
+        println!(
+            "Watching {} for changes (press Ctrl-C to stop)...",
+            describe_roots(roots)
+        );
+        let changed = watcher.wait_for_changes();
+        println!();
+        for path in &changed {
+            println!("Changed: {}", path.display());
+        }
+
+        if changed.iter().any(|path| config_files.contains(path)) {
+            let reloaded = load_cli_config(args).and_then(|config| {
+                watcher.set_config(&config)?;
+                Ok(config)
+            });
+            match reloaded {
+                Ok(config) => app = App::new(config),
+                Err(e) => eprintln!("Error: {e:#}; keeping the previous configuration"),
+            }
+        }
+
+        regenerate(&app, args, roots);
+    }
+}
+
+/// Run the app once in watch mode, reporting errors instead of exiting
+#[cfg(feature = "cli")]
+fn regenerate(app: &App, args: &Cli, roots: &[CourseRoot]) {
+    match app.run_roots(roots, args.output_path(), &args.format_str()) {
+        Ok(diagnostics) => {
+            print_diagnostics(&diagnostics);
+            println!("Course map written to {}", args.output_path());
+        }
+        Err(e) => eprintln!("Error: {e:#}"),
+    }
+}
+
+/// The input directories, for messages
+#[cfg(feature = "cli")]
+fn describe_roots(roots: &[CourseRoot]) -> String {
+    roots
+        .iter()
+        .map(|root| root.dir.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(feature = "cli")]
+fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        let label = if diagnostic.is_error() {
+            "Error"
+        } else {
+            "Warning"
+        };
+        eprintln!("{label}: {}", diagnostic.message());
+        for file in &diagnostic.files {
+            eprintln!("  --> {}", file.display());
+        }
+    }
+}
+
 #[cfg(not(feature = "cli"))]
 fn main() {
     panic!("This binary requires the 'cli' feature to be enabled");
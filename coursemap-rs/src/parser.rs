@@ -1,14 +1,20 @@
 //! Document parsing functionality for extracting course metadata
 
 use anyhow::{Context, Result};
-use gray_matter::{engine::YAML, Matter};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::Mutex;
 
-use crate::config::Config;
+use crate::cache::{self, CacheEntry, ParseCache};
+use crate::config::{Config, FileMatcher, InputMode};
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::extractors::{normalize_extension, Extractors};
+use crate::graph::EdgeKind;
+use crate::quarto;
+use crate::roots;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Document {
@@ -17,7 +23,19 @@ pub struct Document {
     pub file_path: PathBuf,
     pub phase: String,
     pub prerequisites: Vec<String>,
+    /// Courses worth taking first, drawn as dashed edges
+    #[serde(default)]
+    pub recommended: Vec<String>,
+    /// Courses taken together with this one
+    #[serde(default)]
+    pub corequisites: Vec<String>,
     pub metadata: HashMap<String, serde_yaml::Value>,
+    /// Position in the project's navigation, when read in Quarto input mode
+    #[serde(default)]
+    pub order: Option<usize>,
+    /// Course the document belongs to, when several are read together
+    #[serde(default)]
+    pub namespace: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +43,8 @@ struct CourseMapMetadata {
     pub id: String,
     pub phase: Option<String>,
     pub prerequisites: Option<Vec<String>>,
+    pub recommended: Option<Vec<String>>,
+    pub corequisites: Option<Vec<String>>,
 }
 
 impl Document {
@@ -43,10 +63,41 @@ impl Document {
             file_path,
             phase,
             prerequisites,
+            recommended: Vec::new(),
+            corequisites: Vec::new(),
             metadata,
+            order: None,
+            namespace: None,
         }
     }
 
+    /// Put the document in the course `namespace`
+    ///
+    /// Its id and every unqualified prerequisite, recommended course and
+    /// corequisite are qualified with the namespace (see [`roots::qualify`]).
+    pub fn set_namespace(&mut self, namespace: &str) {
+        self.id = roots::qualify(namespace, &self.id);
+        for references in [
+            &mut self.prerequisites,
+            &mut self.recommended,
+            &mut self.corequisites,
+        ] {
+            for reference in references.iter_mut() {
+                *reference = roots::qualify(namespace, reference);
+            }
+        }
+        self.namespace = Some(namespace.to_string());
+    }
+
+    /// The listed courses this document depends on, by kind of dependency
+    pub fn dependencies(&self) -> [(EdgeKind, &[String]); 3] {
+        [
+            (EdgeKind::Required, &self.prerequisites),
+            (EdgeKind::Recommended, &self.recommended),
+            (EdgeKind::Corequisite, &self.corequisites),
+        ]
+    }
+
     /// Get the display name for this document
     pub fn display_name(&self) -> String {
         if self.title.is_empty() {
@@ -57,130 +108,509 @@ impl Document {
     }
 }
 
+/// A file (or directory) that could not be read or parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFailure {
+    pub file_path: PathBuf,
+    /// Why the file was skipped, without the file name
+    pub message: String,
+}
+
+impl ParseFailure {
+    fn new(file_path: &Path, error: &anyhow::Error) -> Self {
+        // The outermost context only repeats the file name
+        let causes: Vec<String> = error.chain().map(|cause| cause.to_string()).collect();
+        let message = if causes.len() > 1 {
+            causes[1..].join(": ")
+        } else {
+            causes.join("")
+        };
+
+        Self {
+            file_path: file_path.to_path_buf(),
+            message,
+        }
+    }
+}
+
+/// Documents parsed from a directory, plus the files that failed
+#[derive(Debug, Clone, Default)]
+pub struct ParseReport {
+    pub documents: Vec<Document>,
+    pub failures: Vec<ParseFailure>,
+}
+
+impl ParseReport {
+    /// One error diagnostic per failed file
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.failures
+            .iter()
+            .map(|failure| {
+                Diagnostic::error(
+                    DiagnosticKind::ParseError {
+                        message: failure.message.clone(),
+                    },
+                    vec![failure.file_path.clone()],
+                )
+            })
+            .collect()
+    }
+}
+
 /// Parse all documents in a directory
-pub fn parse_directory(dir_path: &str, config: &Config) -> Result<Vec<Document>> {
-    let mut documents = Vec::new();
+///
+/// Files matched by the `ignore` patterns, outside the `include` patterns, or
+/// listed in a `.gitignore` or `.quartoignore` file are skipped. With
+/// [`InputMode::Quarto`] only the documents listed in the project's
+/// `_quarto.yml` are read, in their listed order, and each document records
+/// its position in [`Document::order`]. Files that cannot be read or parsed
+/// (including listed files that do not exist) are collected in
+/// [`ParseReport::failures`], unless `config.strict` is set, in which case
+/// the first failure is returned as an error.
+///
+/// With `config.cache` set, documents are kept in a `.coursemap-cache` file
+/// and only files that changed since the last run are parsed again (see the
+/// [`cache`] module).
+///
+/// The directory is walked and the documents are parsed in parallel on the
+/// current rayon thread pool. The result does not depend on the number of
+/// threads: in directory mode documents are in path order.
+pub fn parse_directory(dir_path: &str, config: &Config) -> Result<ParseReport> {
+    parse_directory_with(dir_path, config, &Extractors::default())
+}
+
+/// Parse all documents in a directory with the given frontmatter extractors
+pub fn parse_directory_with(
+    dir_path: &str,
+    config: &Config,
+    extractors: &Extractors,
+) -> Result<ParseReport> {
+    let mut report = ParseReport::default();
     let dir = Path::new(dir_path);
 
     if !dir.exists() {
         return Err(anyhow::anyhow!("Directory does not exist: {}", dir_path));
     }
 
-    for entry in WalkDir::new(dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
-        let path = entry.path();
+    let matcher = config.file_matcher(dir)?;
+    let files = match config.input_mode {
+        InputMode::Directory => walk_directory(dir, &matcher, config, &mut report)?,
+        InputMode::Quarto => quarto::project_files(dir, &config.extensions, &matcher)?,
+    };
+
+    // Pick the documents and look up their inherited defaults first; the
+    // cascade caches each directory, so this is cheap
+    let mut cascade = MetadataCascade::new(dir, &config.root_key);
+    let mut candidates = Vec::new();
+    for (position, path) in files.into_iter().enumerate() {
+        if matcher.is_excluded(&path) || !is_course_document(&path, &config.extensions) {
+            continue;
+        }
+
+        let defaults = cascade.defaults_for(path.parent().unwrap_or(dir));
+        for (metadata_path, e) in cascade.errors.drain(..) {
+            if config.strict {
+                return Err(e);
+            }
+            report.failures.push(ParseFailure::new(&metadata_path, &e));
+        }
+        candidates.push((position, path, defaults));
+    }
+
+    let cache = config
+        .cache
+        .then(|| ParseCache::load(dir, config, extractors));
+
+    // Results come back in the order of `candidates`
+    let results: Vec<_> = candidates
+        .par_iter()
+        .map(|(position, path, defaults)| {
+            let (mut doc, cached) = match &cache {
+                Some(cache) => {
+                    let (doc, cached) =
+                        parse_cached(cache, dir, path, config, extractors, defaults.as_ref())?;
+                    (doc, Some(cached))
+                }
+                None => {
+                    let doc =
+                        parse_document_with_defaults(path, config, extractors, defaults.as_ref())?;
+                    (doc, None)
+                }
+            };
+            if config.input_mode == InputMode::Quarto {
+                doc.order = Some(*position);
+            }
+            Ok((doc, cached))
+        })
+        .collect();
+
+    let mut updated = ParseCache::new(config, extractors);
+    let mut stale = false;
+    for ((_, path, _), result) in candidates.iter().zip(results) {
+        match result {
+            Ok((doc, cached)) => {
+                if let Some((key, entry, hit)) = cached {
+                    stale |= !hit;
+                    updated.entries.insert(key, entry);
+                }
+                report.documents.push(doc)
+            }
+            Err(e) if config.strict => return Err(e),
+            Err(e) => report.failures.push(ParseFailure::new(path, &e)),
+        }
+    }
+
+    // Rewrite the cache when documents were parsed, added or removed
+    if let Some(cache) = cache {
+        if stale || updated.entries.len() != cache.entries.len() {
+            if let Err(e) = updated.save(dir) {
+                log::warn!("Could not save the parse cache: {e:#}");
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Parse a document through the cache
+///
+/// Returns the document with the cache key, the new cache entry and whether
+/// the entry was up to date.
+fn parse_cached(
+    cache: &ParseCache,
+    dir: &Path,
+    path: &Path,
+    config: &Config,
+    extractors: &Extractors,
+    defaults: Option<&serde_yaml::Value>,
+) -> Result<(Document, (String, CacheEntry, bool))> {
+    let key = path
+        .strip_prefix(dir)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let defaults_hash = defaults.map_or(0, |defaults| {
+        let yaml = serde_yaml::to_string(defaults).unwrap_or_default();
+        cache::hash_bytes(yaml.as_bytes())
+    });
+
+    let (mut entry, hit) = cache.get_or_parse(path, &key, defaults_hash, |content| {
+        parse_content(path, content, config, extractors, defaults)
+    })?;
+
+    // The input directory may be spelled differently from the last run
+    entry.document.file_path = path.to_path_buf();
+    entry.document.order = None;
+
+    Ok((entry.document.clone(), (key, entry, hit)))
+}
+
+/// Every file under `dir` that is not in an ignored directory or listed in
+/// an ignore file, sorted by path, recording directories that cannot be read
+///
+/// Directories are read in parallel, with as many threads as the current
+/// rayon thread pool.
+fn walk_directory(
+    dir: &Path,
+    matcher: &FileMatcher,
+    config: &Config,
+    report: &mut ParseReport,
+) -> Result<Vec<PathBuf>> {
+    let walker = walker(dir, matcher)
+        .threads(rayon::current_num_threads())
+        .build_parallel();
+
+    let files = Mutex::new(Vec::new());
+    let errors = Mutex::new(Vec::new());
+    walker.run(|| {
+        Box::new(|entry| {
+            match entry {
+                Ok(entry) if entry.file_type().is_some_and(|t| t.is_file()) => {
+                    files.lock().unwrap().push(entry.into_path());
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    let path = walk_error_path(&e).unwrap_or(dir).to_path_buf();
+                    errors.lock().unwrap().push((path, e));
+                }
+            }
+            ignore::WalkState::Continue
+        })
+    });
+
+    let mut errors = errors.into_inner().unwrap();
+    errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (path, e) in errors {
+        let error = anyhow::Error::new(e).context(format!("Failed to read {}", path.display()));
+        if config.strict {
+            return Err(error);
+        }
+        report.failures.push(ParseFailure::new(&path, &error));
+    }
+
+    let mut files = files.into_inner().unwrap();
+    files.sort();
+    Ok(files)
+}
+
+/// A walker over `dir` that skips `.git`, the directories `matcher` ignores
+/// and everything listed in `.gitignore` and `.quartoignore` files
+pub(crate) fn walker(dir: &Path, matcher: &FileMatcher) -> ignore::WalkBuilder {
+    let matcher = matcher.clone();
+    let mut builder = ignore::WalkBuilder::new(dir);
+    builder
+        .hidden(false)
+        .git_global(false)
+        .require_git(false)
+        .add_custom_ignore_filename(".quartoignore")
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            entry.file_name() != ".git" && !(is_dir && matcher.is_ignored_dir(entry.path()))
+        });
+    builder
+}
+
+/// The file or directory a walk error is about, if it names one
+fn walk_error_path(error: &ignore::Error) -> Option<&Path> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            walk_error_path(err)
+        }
+        _ => None,
+    }
+}
+
+/// Course map defaults inherited from `_quarto.yml` and `_metadata.yml`
+///
+/// As in Quarto, the `root-key` block of the project's `_quarto.yml` applies
+/// to every document, and the block of a directory's `_metadata.yml` applies
+/// to the documents in that directory and below it, with deeper files
+/// overriding shallower ones.
+struct MetadataCascade<'a> {
+    root: PathBuf,
+    root_key: &'a str,
+    cache: HashMap<PathBuf, Option<serde_yaml::Value>>,
+    /// Metadata files that could not be read, not yet reported
+    errors: Vec<(PathBuf, anyhow::Error)>,
+}
+
+impl<'a> MetadataCascade<'a> {
+    fn new(root: &Path, root_key: &'a str) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            root_key,
+            cache: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Merged defaults for documents in `dir`
+    ///
+    /// A metadata file that cannot be read is added to `errors` once and then
+    /// treated as empty.
+    fn defaults_for(&mut self, dir: &Path) -> Option<serde_yaml::Value> {
+        if let Some(defaults) = self.cache.get(dir) {
+            return defaults.clone();
+        }
 
-        // Check if file should be ignored
-        if let Some(path_str) = path.to_str() {
-            if config.should_ignore(path_str) {
-                continue;
+        let (mut defaults, metadata_files) = match dir.parent() {
+            Some(parent) if dir != self.root && dir.starts_with(&self.root) => {
+                (self.defaults_for(parent), vec![dir.join("_metadata.yml")])
+            }
+            _ => (
+                None,
+                vec![dir.join("_quarto.yml"), dir.join("_metadata.yml")],
+            ),
+        };
+
+        for metadata_file in metadata_files {
+            match read_metadata_block(&metadata_file, self.root_key) {
+                Ok(Some(block)) => match defaults.as_mut() {
+                    Some(defaults) => merge_yaml(defaults, block),
+                    None => defaults = Some(block),
+                },
+                Ok(None) => {}
+                Err(e) => self.errors.push((metadata_file, e)),
             }
         }
 
-        // Check if file has a supported extension
-        if let Some(extension) = path.extension() {
-            let ext = extension.to_string_lossy().to_lowercase();
-            if matches!(ext.as_str(), "qmd" | "md" | "rmd") {
-                if let Ok(doc) = parse_document(path, config) {
-                    documents.push(doc);
+        self.cache.insert(dir.to_path_buf(), defaults.clone());
+        defaults
+    }
+}
+
+/// Read the `root_key` block of a Quarto metadata file, if it exists
+fn read_metadata_block(path: &Path, root_key: &str) -> Result<Option<serde_yaml::Value>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let metadata: Option<serde_yaml::Mapping> = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    Ok(metadata.and_then(|mut metadata| metadata.remove(root_key)))
+}
+
+/// Merge `overrides` into `base`, recursing into mappings
+///
+/// A null override (such as an empty `course-map:` block) keeps `base`.
+fn merge_yaml(base: &mut serde_yaml::Value, overrides: serde_yaml::Value) {
+    match (base, overrides) {
+        (_, serde_yaml::Value::Null) => {}
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_yaml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
                 }
             }
         }
+        (base, overrides) => *base = overrides,
     }
+}
+
+/// Check whether a file is a Quarto metadata file read by the cascade
+pub fn is_metadata_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == "_quarto.yml" || name == "_metadata.yml")
+}
+
+/// Check whether a file lists paths to leave out of the course
+pub fn is_ignore_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == ".gitignore" || name == ".quartoignore")
+}
 
-    Ok(documents)
+/// Check whether a file has one of the course document `extensions`
+///
+/// Extensions are compared case-insensitively, with or without a dot.
+pub fn is_course_document(path: &Path, extensions: &[String]) -> bool {
+    path.extension().is_some_and(|extension| {
+        let extension = normalize_extension(&extension.to_string_lossy());
+        extensions
+            .iter()
+            .any(|candidate| normalize_extension(candidate) == extension)
+    })
 }
 
 /// Parse a single document file
 pub fn parse_document(file_path: &Path, config: &Config) -> Result<Document> {
+    parse_document_with_defaults(file_path, config, &Extractors::default(), None)
+}
+
+/// Parse a single document file, applying inherited course map defaults
+///
+/// The frontmatter is read by the extractor registered for the file's
+/// extension. `defaults` is merged under the document's own `root-key` block,
+/// so values in the document win. Documents without their own block are not
+/// affected.
+pub fn parse_document_with_defaults(
+    file_path: &Path,
+    config: &Config,
+    extractors: &Extractors,
+    defaults: Option<&serde_yaml::Value>,
+) -> Result<Document> {
     let content = fs::read_to_string(file_path)
         .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
 
-    let matter = Matter::<YAML>::new();
-    let result = matter.parse(&content);
+    parse_content(file_path, &content, config, extractors, defaults)
+}
+
+/// Parse the already read `content` of `file_path`
+fn parse_content(
+    file_path: &Path,
+    content: &str,
+    config: &Config,
+    extractors: &Extractors,
+    defaults: Option<&serde_yaml::Value>,
+) -> Result<Document> {
+    let frontmatter = extractors
+        .for_path(file_path)
+        .extract(content, config)
+        .with_context(|| format!("Failed to parse {}", file_path.display()))?;
 
     // Extract basic metadata
     let mut metadata: HashMap<String, serde_yaml::Value> = HashMap::new();
     let mut title = String::new();
     let mut course_map_data: Option<CourseMapMetadata> = None;
 
-    if let Some(_front_matter) = result.data {
-        // Parse the front matter as YAML directly from the original content
-        // Extract the YAML front matter section manually
-        let lines: Vec<&str> = content.lines().collect();
-        let mut yaml_content = String::new();
-        let mut in_frontmatter = false;
-
-        for line in lines {
-            if line.trim() == "---" {
-                if !in_frontmatter {
-                    in_frontmatter = true;
-                    continue;
-                } else {
-                    break;
-                }
-            }
+    for (key, mut value) in frontmatter.unwrap_or_default() {
+        let serde_yaml::Value::String(key_str) = key else {
+            continue;
+        };
 
-            if in_frontmatter {
-                yaml_content.push_str(line);
-                yaml_content.push('\n');
+        if key_str == config.root_key {
+            if let Some(defaults) = defaults {
+                let own = std::mem::replace(&mut value, defaults.clone());
+                merge_yaml(&mut value, own);
             }
         }
 
-        if !yaml_content.is_empty() {
-            if let Ok(serde_yaml::Value::Mapping(map)) =
-                serde_yaml::from_str::<serde_yaml::Value>(&yaml_content)
-            {
-                for (key, value) in map {
-                    if let serde_yaml::Value::String(key_str) = key {
-                        metadata.insert(key_str.clone(), value.clone());
-
-                        // Extract title
-                        if key_str == "title" {
-                            if let serde_yaml::Value::String(ref title_str) = value {
-                                title = title_str.clone();
-                            }
-                        }
-
-                        // Extract course-map metadata
-                        if key_str == config.root_key {
-                            if let Ok(cm_data) = serde_yaml::from_value::<CourseMapMetadata>(value)
-                            {
-                                course_map_data = Some(cm_data);
-                            }
-                        }
-                    }
-                }
+        // Extract title
+        if key_str == "title" {
+            if let serde_yaml::Value::String(ref title_str) = value {
+                title = title_str.clone();
             }
         }
+
+        // Extract course-map metadata
+        if key_str == config.root_key {
+            let cm_data = serde_yaml::from_value::<CourseMapMetadata>(value.clone())
+                .map_err(|e| anyhow::anyhow!("invalid '{}' metadata: {e}", config.root_key))
+                .with_context(|| format!("Failed to parse {}", file_path.display()))?;
+            course_map_data = Some(cm_data);
+        }
+
+        metadata.insert(key_str, value);
     }
 
     // Extract course map information
-    let (id, phase, prerequisites) = if let Some(cm_data) = course_map_data {
-        let phase = cm_data.phase.unwrap_or_else(|| "Unknown".to_string());
-        let prerequisites = cm_data.prerequisites.unwrap_or_default();
-        (cm_data.id, phase, prerequisites)
-    } else {
-        // Fallback: use filename as ID
-        let filename = file_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown")
-            .to_string();
-        (filename, "Unknown".to_string(), Vec::new())
-    };
-
-    Ok(Document::new(
-        id,
-        title,
-        file_path.to_path_buf(),
-        phase,
-        prerequisites,
-        metadata,
-    ))
+    let (id, phase, prerequisites, recommended, corequisites) =
+        if let Some(cm_data) = course_map_data {
+            let phase = cm_data.phase.unwrap_or_else(|| "Unknown".to_string());
+            let prerequisites = cm_data.prerequisites.unwrap_or_default();
+            (
+                cm_data.id,
+                phase,
+                prerequisites,
+                cm_data.recommended.unwrap_or_default(),
+                cm_data.corequisites.unwrap_or_default(),
+            )
+        } else {
+            // Fallback: use filename as ID
+            let filename = file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+            (
+                filename,
+                "Unknown".to_string(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+            )
+        };
+
+    Ok(Document {
+        recommended,
+        corequisites,
+        ..Document::new(
+            id,
+            title,
+            file_path.to_path_buf(),
+            phase,
+            prerequisites,
+            metadata,
+        )
+    })
 }
 
 #[cfg(test)]
@@ -200,6 +630,8 @@ course-map:
   id: test-course
   phase: Pre
   prerequisites: ["intro"]
+  recommended: [reading]
+  corequisites: [lab]
 ---
 
 # Test Course Content
@@ -213,6 +645,8 @@ course-map:
         assert_eq!(doc.title, "Test Course");
         assert_eq!(doc.phase, "Pre");
         assert_eq!(doc.prerequisites, vec!["intro"]);
+        assert_eq!(doc.recommended, vec!["reading"]);
+        assert_eq!(doc.corequisites, vec!["lab"]);
 
         Ok(())
     }
@@ -233,6 +667,111 @@ course-map:
         Ok(())
     }
 
+    #[test]
+    fn test_parse_document_frontmatter_variants() -> Result<()> {
+        let config = Config::default();
+        let cases = [
+            "\n\n---\ntitle: Leading blank lines\ncourse-map:\n  id: c\n---\n",
+            "---\ntitle: Dots\ncourse-map:\n  id: c\n...\n# Body\n",
+            "\u{feff}--- \r\ntitle: CRLF\r\ncourse-map:\r\n  id: c\r\n---  \r\n# Body\r\n",
+        ];
+
+        for content in cases {
+            let temp_file = NamedTempFile::with_suffix(".qmd")?;
+            std::fs::write(temp_file.path(), content)?;
+
+            let doc = parse_document(temp_file.path(), &config)?;
+            assert_eq!(doc.id, "c", "{content:?}");
+            assert!(!doc.title.is_empty(), "{content:?}");
+        }
+
+        // Horizontal rules in a document without frontmatter are not metadata
+        let temp_file = NamedTempFile::with_suffix(".md")?;
+        std::fs::write(
+            temp_file.path(),
+            "# Notes\n\n---\n\ncourse-map: text\n\n---\n",
+        )?;
+        let doc = parse_document(temp_file.path(), &config)?;
+        assert!(doc.metadata.is_empty());
+        assert_eq!(doc.phase, "Unknown");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_document_malformed_yaml() -> Result<()> {
+        let temp_file = NamedTempFile::with_suffix(".qmd")?;
+        std::fs::write(
+            temp_file.path(),
+            "---\ntitle: \"Broken\ncourse-map:\n  id: broken\n---\n",
+        )?;
+
+        let config = Config::default();
+        let error = parse_document(temp_file.path(), &config).unwrap_err();
+        let message = format!("{error:#}");
+        assert!(
+            message.contains("invalid YAML frontmatter at line"),
+            "{message}"
+        );
+        assert!(message.contains(&temp_file.path().display().to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_notebook() -> Result<()> {
+        let config = Config::default();
+
+        // Frontmatter in the first raw cell, with the source as a list of lines
+        let temp_file = NamedTempFile::with_suffix(".ipynb")?;
+        std::fs::write(
+            temp_file.path(),
+            r#"{
+  "cells": [
+    {"cell_type": "markdown", "metadata": {}, "source": ["---\n", "title: Not me\n", "---\n"]},
+    {"cell_type": "raw", "metadata": {}, "source": [
+      "---\n", "title: \"Notebook Lesson\"\n", "course-map:\n", "  id: nb\n",
+      "  phase: InClass\n", "  prerequisites: [intro]\n", "---\n"
+    ]},
+    {"cell_type": "code", "metadata": {}, "source": "print(1)", "outputs": [], "execution_count": null}
+  ],
+  "metadata": {"kernelspec": {"name": "python3"}},
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"#,
+        )?;
+        let doc = parse_document(temp_file.path(), &config)?;
+        assert_eq!(doc.id, "nb");
+        assert_eq!(doc.title, "Notebook Lesson");
+        assert_eq!(doc.phase, "InClass");
+        assert_eq!(doc.prerequisites, vec!["intro"]);
+        assert!(!doc.metadata.contains_key("kernelspec"));
+
+        // Course map in the notebook metadata, as a single source string
+        let temp_file = NamedTempFile::with_suffix(".ipynb")?;
+        std::fs::write(
+            temp_file.path(),
+            r#"{
+  "cells": [{"cell_type": "raw", "metadata": {}, "source": "---\ntitle: From Cell\n---\n"}],
+  "metadata": {"title": "From Metadata", "course-map": {"id": "meta", "phase": "Post"}},
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"#,
+        )?;
+        let doc = parse_document(temp_file.path(), &config)?;
+        assert_eq!(doc.id, "meta");
+        assert_eq!(doc.title, "From Cell");
+        assert_eq!(doc.phase, "Post");
+
+        // Broken notebooks are reported rather than skipped silently
+        let temp_file = NamedTempFile::with_suffix(".ipynb")?;
+        std::fs::write(temp_file.path(), "{\"cells\": [")?;
+        let error = parse_document(temp_file.path(), &config).unwrap_err();
+        assert!(format!("{error:#}").contains("invalid notebook JSON"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_document_display_name() {
         let doc = Document::new(
@@ -297,7 +836,7 @@ course-map:
         std::fs::write(dir_path.join("readme.txt"), "Not a course file")?;
 
         let config = Config::default();
-        let documents = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        let documents = parse_directory(dir_path.to_str().unwrap(), &config)?.documents;
 
         assert_eq!(documents.len(), 2);
 
@@ -314,6 +853,338 @@ course-map:
         Ok(())
     }
 
+    #[test]
+    fn test_parse_directory_reports_failures() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path();
+
+        std::fs::write(
+            dir_path.join("good.qmd"),
+            "---\ncourse-map:\n  id: good\n---\n",
+        )?;
+        std::fs::write(dir_path.join("broken.qmd"), "---\ntitle: [oops\n---\n")?;
+        std::fs::write(
+            dir_path.join("no-id.qmd"),
+            "---\ncourse-map:\n  phase: Pre\n---\n",
+        )?;
+        std::fs::write(dir_path.join("binary.md"), [0xff, 0xfe, 0x00])?;
+
+        let config = Config::default();
+        let report = parse_directory(dir_path.to_str().unwrap(), &config)?;
+
+        assert_eq!(report.documents.len(), 1);
+        assert_eq!(report.documents[0].id, "good");
+
+        let mut failures = report.failures.clone();
+        failures.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        assert_eq!(failures.len(), 3);
+        assert_eq!(failures[0].file_path, dir_path.join("binary.md"));
+        assert_eq!(failures[1].file_path, dir_path.join("broken.qmd"));
+        assert!(
+            failures[1]
+                .message
+                .starts_with("invalid YAML frontmatter at line"),
+            "{}",
+            failures[1].message
+        );
+        assert_eq!(
+            failures[2].message,
+            "invalid 'course-map' metadata: missing field `id`"
+        );
+
+        let diagnostics = report.diagnostics();
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.code() == "parse-error" && d.is_error()));
+
+        // Strict mode stops at the first failure
+        let config = Config {
+            strict: true,
+            ..Config::default()
+        };
+        assert!(parse_directory(dir_path.to_str().unwrap(), &config).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_directory_metadata_cascade() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path();
+        std::fs::create_dir_all(dir_path.join("week3/extra"))?;
+
+        std::fs::write(
+            dir_path.join("_quarto.yml"),
+            "project:\n  type: website\ncourse-map:\n  phase: Pre\n  prerequisites: [intro]\n",
+        )?;
+        std::fs::write(
+            dir_path.join("week3/_metadata.yml"),
+            "course-map:\n  phase: InClass\n",
+        )?;
+        std::fs::write(
+            dir_path.join("intro.qmd"),
+            "---\ncourse-map:\n  id: intro\n  prerequisites: []\n---\n",
+        )?;
+        std::fs::write(
+            dir_path.join("week3/lab.qmd"),
+            "---\ncourse-map:\n  id: lab\n---\n",
+        )?;
+        std::fs::write(
+            dir_path.join("week3/extra/quiz.qmd"),
+            "---\ncourse-map:\n  id: quiz\n  phase: Post\n---\n",
+        )?;
+        std::fs::write(dir_path.join("week3/notes.md"), "# No course map here\n")?;
+
+        let config = Config::default();
+        let report = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        assert!(report.failures.is_empty());
+
+        let find = |id: &str| report.documents.iter().find(|d| d.id == id).unwrap();
+        assert_eq!(find("intro").phase, "Pre");
+        assert!(find("intro").prerequisites.is_empty());
+        assert_eq!(find("lab").phase, "InClass");
+        assert_eq!(find("lab").prerequisites, vec!["intro"]);
+        assert_eq!(find("quiz").phase, "Post");
+        assert!(!find("notes").metadata.contains_key("course-map"));
+
+        // A broken metadata file is reported once and otherwise ignored
+        std::fs::write(dir_path.join("week3/_metadata.yml"), "course-map: [")?;
+        let report = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(
+            report.failures[0].file_path,
+            dir_path.join("week3/_metadata.yml")
+        );
+        assert_eq!(
+            report
+                .documents
+                .iter()
+                .find(|d| d.id == "lab")
+                .unwrap()
+                .phase,
+            "Pre"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_directory_quarto_input_mode() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path();
+        std::fs::create_dir_all(dir_path.join("week1"))?;
+
+        std::fs::write(
+            dir_path.join("_quarto.yml"),
+            r#"
+project:
+  type: book
+book:
+  chapters:
+    - index.qmd
+    - week1/lab.qmd
+    - intro.qmd
+    - missing.qmd
+course-map:
+  phase: Pre
+"#,
+        )?;
+        std::fs::write(dir_path.join("index.qmd"), "# Home\n")?;
+        std::fs::write(
+            dir_path.join("intro.qmd"),
+            "---\ncourse-map:\n  id: intro\n---\n",
+        )?;
+        std::fs::write(
+            dir_path.join("week1/lab.qmd"),
+            "---\ncourse-map:\n  id: lab\n  prerequisites: [intro]\n---\n",
+        )?;
+        std::fs::write(
+            dir_path.join("draft.qmd"),
+            "---\ncourse-map:\n  id: draft\n---\n",
+        )?;
+
+        let config = Config {
+            input_mode: InputMode::Quarto,
+            ..Config::default()
+        };
+        let report = parse_directory(dir_path.to_str().unwrap(), &config)?;
+
+        // Listed order, without the unlisted draft or the ignored index
+        let ids: Vec<&str> = report.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["lab", "intro"]);
+        assert_eq!(report.documents[0].order, Some(1));
+        assert_eq!(report.documents[1].order, Some(2));
+        assert_eq!(report.documents[0].phase, "Pre");
+
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].file_path, dir_path.join("missing.qmd"));
+
+        // Directory mode reads everything and leaves the order unset
+        let report = parse_directory(dir_path.to_str().unwrap(), &Config::default())?;
+        assert_eq!(report.documents.len(), 3);
+        assert!(report.documents.iter().all(|d| d.order.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_directory_ignore_files() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path();
+        for sub in ["drafts/old", "lessons", "_site"] {
+            std::fs::create_dir_all(dir_path.join(sub))?;
+        }
+        for file in [
+            "index.qmd",
+            "drafts/idea.qmd",
+            "drafts/old/keep.qmd",
+            "lessons/intro.qmd",
+            "lessons/scratch.md",
+            "lessons/notREADME.md",
+            "_site/intro.md",
+        ] {
+            std::fs::write(dir_path.join(file), "# Lesson\n")?;
+        }
+        std::fs::write(dir_path.join(".gitignore"), "_site/\n")?;
+        std::fs::write(dir_path.join("lessons/.quartoignore"), "scratch.md\n")?;
+
+        let config = Config {
+            ignore: vec![
+                "index.qmd".to_string(),
+                "drafts/**/*.qmd".to_string(),
+                "!drafts/**/keep.qmd".to_string(),
+                "README.md".to_string(),
+            ],
+            ..Config::default()
+        };
+        let report = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        let mut ids: Vec<&str> = report.documents.iter().map(|d| d.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["intro", "keep", "notREADME"]);
+
+        let config = Config {
+            include: vec!["lessons/".to_string()],
+            ..config
+        };
+        let report = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        let mut ids: Vec<&str> = report.documents.iter().map(|d| d.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["intro", "notREADME"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_directory_order_is_deterministic() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path();
+        for week in 0..5 {
+            std::fs::create_dir_all(dir_path.join(format!("week{week}")))?;
+            for lesson in 0..20 {
+                std::fs::write(
+                    dir_path.join(format!("week{week}/lesson{lesson:02}.qmd")),
+                    format!("---\ncourse-map:\n  id: w{week}-l{lesson}\n---\n"),
+                )?;
+            }
+        }
+        std::fs::write(dir_path.join("week3/broken.qmd"), "---\ntitle: [\n---\n")?;
+
+        let parse_with_threads = |threads: usize| -> Result<ParseReport> {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?
+                .install(|| parse_directory(dir_path.to_str().unwrap(), &Config::default()))
+        };
+
+        let sequential = parse_with_threads(1)?;
+        let paths: Vec<&PathBuf> = sequential.documents.iter().map(|d| &d.file_path).collect();
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths.len(), 100);
+        assert_eq!(paths, sorted);
+        assert_eq!(sequential.failures.len(), 1);
+
+        for _ in 0..3 {
+            let parallel = parse_with_threads(4)?;
+            let parallel_paths: Vec<&PathBuf> =
+                parallel.documents.iter().map(|d| &d.file_path).collect();
+            assert_eq!(parallel_paths, paths);
+            assert_eq!(parallel.failures, sequential.failures);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_directory_with_cache() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path();
+        std::fs::create_dir_all(dir_path.join("week1"))?;
+        std::fs::write(
+            dir_path.join("intro.qmd"),
+            "---\ncourse-map:\n  id: intro\n---\n",
+        )?;
+        std::fs::write(
+            dir_path.join("week1/lab.qmd"),
+            "---\ncourse-map:\n  id: lab\n---\n",
+        )?;
+
+        let config = Config {
+            cache: true,
+            ..Config::default()
+        };
+        let first = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        assert_eq!(first.documents.len(), 2);
+        let cache = ParseCache::load(dir_path, &config, &Extractors::default());
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.entries.contains_key("week1/lab.qmd"));
+
+        // A cached document is returned even though the file is not re-read
+        let mut cache = cache;
+        cache.entries.get_mut("intro.qmd").unwrap().document.title = "Cached".to_string();
+        cache.save(dir_path)?;
+        let second = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        assert_eq!(second.documents[0].title, "Cached");
+        assert_eq!(second.documents[0].file_path, dir_path.join("intro.qmd"));
+
+        // Changes to a file or to the inherited defaults are picked up
+        std::fs::write(
+            dir_path.join("intro.qmd"),
+            "---\ntitle: Edited\ncourse-map:\n  id: intro\n---\n",
+        )?;
+        std::fs::write(
+            dir_path.join("week1/_metadata.yml"),
+            "course-map:\n  phase: Post\n",
+        )?;
+        std::fs::remove_file(dir_path.join(cache::CACHE_FILE_NAME))?;
+        parse_directory(dir_path.to_str().unwrap(), &config)?;
+        std::fs::write(
+            dir_path.join("week1/_metadata.yml"),
+            "course-map:\n  phase: InClass\n",
+        )?;
+        let third = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        assert_eq!(third.documents[0].title, "Edited");
+        assert_eq!(third.documents[1].phase, "InClass");
+
+        // Removed documents are dropped from the cache
+        std::fs::remove_file(dir_path.join("week1/lab.qmd"))?;
+        parse_directory(dir_path.to_str().unwrap(), &config)?;
+        assert_eq!(
+            ParseCache::load(dir_path, &config, &Extractors::default())
+                .entries
+                .len(),
+            1
+        );
+
+        // Without the setting no cache is written
+        let temp_dir = tempfile::tempdir()?;
+        std::fs::write(temp_dir.path().join("a.qmd"), "# A\n")?;
+        parse_directory(temp_dir.path().to_str().unwrap(), &Config::default())?;
+        assert!(!temp_dir.path().join(cache::CACHE_FILE_NAME).exists());
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse_directory_nonexistent() {
         let config = Config::default();
@@ -326,6 +1197,7 @@ course-map:
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn test_parse_document_with_custom_root_key() -> Result<()> {
         let temp_file = NamedTempFile::with_suffix(".qmd")?;
 
@@ -406,9 +1278,9 @@ course-map:
         )?; // Should be ignored
 
         let config = Config::default();
-        let documents = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        let documents = parse_directory(dir_path.to_str().unwrap(), &config)?.documents;
 
-        assert_eq!(documents.len(), 3); // Only .qmd, .md, .rmd files
+        assert_eq!(documents.len(), 3); // Only .qmd, .md, .rmd (and .ipynb) files
 
         let ids: Vec<&String> = documents.iter().map(|d| &d.id).collect();
         assert!(ids.contains(&&"qmd".to_string()));
@@ -417,4 +1289,52 @@ course-map:
 
         Ok(())
     }
+
+    #[test]
+    fn test_configured_extensions() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir_path = temp_dir.path();
+
+        std::fs::write(
+            dir_path.join("lesson.Rmarkdown"),
+            "---\ncourse-map:\n  id: rmarkdown\n---\n",
+        )?;
+        std::fs::write(
+            dir_path.join("guide.adoc"),
+            "---\ncourse-map:\n  id: adoc\n---\n= Guide\n",
+        )?;
+        std::fs::write(
+            dir_path.join("intro.rst"),
+            "..\n   ---\n   course-map:\n     id: rst\n   ---\n\nIntro\n=====\n",
+        )?;
+        std::fs::write(
+            dir_path.join("notes.md"),
+            "---\ncourse-map:\n  id: md\n---\n",
+        )?;
+
+        let config = Config {
+            extensions: vec![
+                "rmarkdown".to_string(),
+                ".adoc".to_string(),
+                "RST".to_string(),
+            ],
+            ..Config::default()
+        };
+        let report = parse_directory(dir_path.to_str().unwrap(), &config)?;
+        assert!(report.failures.is_empty());
+        let mut ids: Vec<&str> = report.documents.iter().map(|d| d.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["adoc", "rmarkdown", "rst"]);
+
+        assert!(is_course_document(
+            Path::new("a/lesson.QMD"),
+            &Config::default().extensions
+        ));
+        assert!(!is_course_document(
+            Path::new("a/lesson.rst"),
+            &Config::default().extensions
+        ));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,266 @@
+//! Quarto project files: which documents belong to a website or book
+//!
+//! Reads `website.sidebar` and `book.chapters` (plus `book.appendices`) from
+//! a project's `_quarto.yml` and lists the documents they reference, in
+//! order.
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::FileMatcher;
+use crate::parser;
+
+/// Documents referenced by the project's navigation, in order
+///
+/// Entries may be file paths, mappings with `href` or `file`, `section` or
+/// `part` groups with nested `contents` or `chapters`, directories (every
+/// document below them), or `auto` (every document in the project). Each
+/// file is listed once, at its first position. Paths are joined to `dir`, and
+/// directories expand to the files with one of the course `extensions`,
+/// walked like directory mode walks them: directories the `matcher` ignores
+/// and files listed in `.gitignore` or `.quartoignore` are left out.
+pub fn project_files(
+    dir: &Path,
+    extensions: &[String],
+    matcher: &FileMatcher,
+) -> Result<Vec<PathBuf>> {
+    let project_file = dir.join("_quarto.yml");
+    let content = fs::read_to_string(&project_file)
+        .with_context(|| format!("Failed to read {}", project_file.display()))?;
+    let project: serde_yaml::Value = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", project_file.display()))?;
+
+    let mut entries = Vec::new();
+    match &project["website"]["sidebar"] {
+        // One sidebar, or several (e.g. one per section of the site)
+        serde_yaml::Value::Sequence(sidebars) => {
+            for sidebar in sidebars {
+                entries.push(&sidebar["contents"]);
+            }
+        }
+        sidebar => entries.push(&sidebar["contents"]),
+    }
+    entries.push(&project["book"]["chapters"]);
+    entries.push(&project["book"]["appendices"]);
+
+    let mut collector = Collector {
+        dir,
+        extensions,
+        matcher,
+        files: Vec::new(),
+        seen: HashSet::new(),
+    };
+    for entry in entries {
+        collector.collect_entry(entry);
+    }
+
+    if collector.files.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} has no website.sidebar or book.chapters entries",
+            project_file.display()
+        ));
+    }
+
+    Ok(collector.files)
+}
+
+/// The files referenced so far, in order, each listed once
+struct Collector<'a> {
+    dir: &'a Path,
+    extensions: &'a [String],
+    matcher: &'a FileMatcher,
+    files: Vec<PathBuf>,
+    seen: HashSet<PathBuf>,
+}
+
+impl Collector<'_> {
+    fn collect_entry(&mut self, entry: &serde_yaml::Value) {
+        match entry {
+            serde_yaml::Value::Sequence(items) => {
+                for item in items {
+                    self.collect_entry(item);
+                }
+            }
+            serde_yaml::Value::String(path) => self.collect_path(path),
+            serde_yaml::Value::Mapping(_) => {
+                for key in ["href", "file"] {
+                    if let Some(path) = entry[key].as_str() {
+                        self.collect_path(path);
+                    }
+                }
+                for key in ["contents", "chapters"] {
+                    self.collect_entry(&entry[key]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_path(&mut self, path: &str) {
+        let path = path.trim();
+
+        // External links and anchors are not project files
+        if path.is_empty() || path.contains("://") || path.starts_with('#') {
+            return;
+        }
+
+        let target = if path == "auto" {
+            self.dir.to_path_buf()
+        } else {
+            self.dir.join(path.trim_start_matches('/'))
+        };
+
+        if target.is_dir() {
+            let mut documents: Vec<PathBuf> = parser::walker(&target, self.matcher)
+                .build()
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    e.file_type().is_some_and(|t| t.is_file())
+                        && parser::is_course_document(e.path(), self.extensions)
+                })
+                .map(|e| e.into_path())
+                .collect();
+            documents.sort();
+            for document in documents {
+                self.push(document);
+            }
+        } else if path.contains('*') {
+            log::warn!("Ignoring unsupported glob '{path}' in _quarto.yml");
+        } else {
+            self.push(target);
+        }
+    }
+
+    fn push(&mut self, path: PathBuf) {
+        if self.seen.insert(path.clone()) {
+            self.files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn matcher(dir: &Path) -> FileMatcher {
+        Config::default().file_matcher(dir).unwrap()
+    }
+
+    #[test]
+    fn test_website_sidebar() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir = temp_dir.path();
+        fs::create_dir_all(dir.join("week2"))?;
+        fs::write(dir.join("week2/b.qmd"), "")?;
+        fs::write(dir.join("week2/a.qmd"), "")?;
+        fs::write(
+            dir.join("_quarto.yml"),
+            r#"
+project:
+  type: website
+website:
+  sidebar:
+    style: docked
+    contents:
+      - index.qmd
+      - href: intro.qmd
+        text: Introduction
+      - section: "Week 1"
+        contents:
+          - week1/lab.qmd
+          - text: Slides
+            href: https://example.org/slides
+      - section: "Week 2"
+        contents: week2
+      - intro.qmd
+"#,
+        )?;
+
+        assert_eq!(
+            project_files(dir, &Config::default().extensions, &matcher(dir))?,
+            vec![
+                dir.join("index.qmd"),
+                dir.join("intro.qmd"),
+                dir.join("week1/lab.qmd"),
+                dir.join("week2/a.qmd"),
+                dir.join("week2/b.qmd"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_book_chapters() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("_quarto.yml"),
+            r#"
+book:
+  chapters:
+    - index.qmd
+    - part: "Basics"
+      chapters:
+        - basics.qmd
+        - notebook.ipynb
+  appendices:
+    - reference.qmd
+"#,
+        )?;
+
+        assert_eq!(
+            project_files(dir, &Config::default().extensions, &matcher(dir))?,
+            vec![
+                dir.join("index.qmd"),
+                dir.join("basics.qmd"),
+                dir.join("notebook.ipynb"),
+                dir.join("reference.qmd"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_navigation() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        assert!(project_files(temp_dir.path(), &[], &matcher(temp_dir.path())).is_err());
+
+        fs::write(
+            temp_dir.path().join("_quarto.yml"),
+            "project:\n  type: default\n",
+        )?;
+        let error = project_files(temp_dir.path(), &[], &matcher(temp_dir.path())).unwrap_err();
+        assert!(error.to_string().contains("no website.sidebar"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_skips_ignored_files() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir = temp_dir.path();
+        for path in ["week1/lab.qmd", "_site/index.qmd", "drafts/next.qmd"] {
+            fs::create_dir_all(dir.join(path).parent().unwrap())?;
+            fs::write(dir.join(path), "")?;
+        }
+        fs::write(dir.join("index.qmd"), "")?;
+        fs::write(dir.join(".gitignore"), "_site/\n")?;
+        fs::write(dir.join(".quartoignore"), "drafts/\n")?;
+        fs::write(
+            dir.join("_quarto.yml"),
+            "website:\n  sidebar:\n    contents:\n      - week1/lab.qmd\n      - auto\n",
+        )?;
+
+        assert_eq!(
+            project_files(dir, &Config::default().extensions, &matcher(dir))?,
+            vec![dir.join("week1/lab.qmd"), dir.join("index.qmd")]
+        );
+
+        Ok(())
+    }
+}
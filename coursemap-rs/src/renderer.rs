@@ -1,12 +1,15 @@
 //! Graph rendering functionality for generating visual output
 
 use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Write;
 use std::fs;
 use std::process::Command;
 
-use crate::config::Config;
-use crate::graph::CourseGraph;
+use crate::config::{Config, Engine};
+use crate::graph::{CourseGraph, CourseNode, EdgeKind};
+use crate::layout;
 
 /// Render a course graph to the specified format
 pub fn render_graph(
@@ -17,8 +20,25 @@ pub fn render_graph(
 ) -> Result<()> {
     match format.to_lowercase().as_str() {
         "dot" => render_dot(graph, output_path, config),
-        "svg" => render_with_graphviz(graph, output_path, "svg", config),
-        "png" => render_with_graphviz(graph, output_path, "png", config),
+        "json" => render_json(graph, output_path, config),
+        "mermaid" => render_mermaid(graph, output_path, config),
+        "html" => render_html(graph, output_path, config),
+        "svg" => match config.engine {
+            Engine::Graphviz => render_with_graphviz(graph, output_path, "svg", config),
+            Engine::Native => render_native_svg(graph, output_path, config),
+            Engine::Auto if graphviz_available() => {
+                render_with_graphviz(graph, output_path, "svg", config)
+            }
+            Engine::Auto => render_native_svg(graph, output_path, config),
+        },
+        "png" => {
+            if config.engine == Engine::Native {
+                return Err(anyhow::anyhow!(
+                    "PNG output requires Graphviz; the native engine only supports SVG"
+                ));
+            }
+            render_with_graphviz(graph, output_path, "png", config)
+        }
         _ => Err(anyhow::anyhow!("Unsupported output format: {}", format)),
     }
 }
@@ -32,6 +52,127 @@ pub fn render_dot(graph: &CourseGraph, output_path: &str, config: &Config) -> Re
     Ok(())
 }
 
+/// Generate Mermaid flowchart output
+pub fn render_mermaid(graph: &CourseGraph, output_path: &str, config: &Config) -> Result<()> {
+    let mermaid_content = generate_mermaid_content(graph, config)?;
+    fs::write(output_path, mermaid_content)
+        .with_context(|| format!("Failed to write Mermaid file: {output_path}"))?;
+
+    Ok(())
+}
+
+/// Generate a standalone interactive HTML page
+pub fn render_html(graph: &CourseGraph, output_path: &str, config: &Config) -> Result<()> {
+    let html_content = generate_html_content(graph, config)?;
+    fs::write(output_path, html_content)
+        .with_context(|| format!("Failed to write HTML file: {output_path}"))?;
+
+    Ok(())
+}
+
+/// Generate JSON graph output
+pub fn render_json(graph: &CourseGraph, output_path: &str, config: &Config) -> Result<()> {
+    let json_content = generate_json_content(graph, config)?;
+    fs::write(output_path, json_content)
+        .with_context(|| format!("Failed to write JSON file: {output_path}"))?;
+
+    Ok(())
+}
+
+/// Version of the JSON graph schema, bumped on incompatible changes
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    version: u32,
+    nodes: Vec<JsonNode<'a>>,
+    edges: Vec<JsonEdge<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonNode<'a> {
+    id: &'a str,
+    title: &'a str,
+    phase: &'a str,
+    color: String,
+    file_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<&'a str>,
+    metadata: BTreeMap<&'a str, &'a serde_yaml::Value>,
+}
+
+#[derive(Serialize)]
+struct JsonEdge<'a> {
+    source: &'a str,
+    target: &'a str,
+    kind: EdgeKind,
+}
+
+/// Generate JSON content from a course graph
+///
+/// The schema is:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "nodes": [
+///     {
+///       "id": "intro",
+///       "title": "Introduction",
+///       "phase": "Pre",
+///       "color": "lightblue",
+///       "file_path": "courses/intro.qmd",
+///       "metadata": { "title": "Introduction", "course-map": { "id": "intro" } }
+///     }
+///   ],
+///   "edges": [ { "source": "intro", "target": "micro", "kind": "required" } ]
+/// }
+/// ```
+///
+/// `metadata` holds the document's full frontmatter with keys sorted, and
+/// edges point from prerequisite to dependent course. An edge's `kind` is
+/// `required`, `recommended` or `corequisite`. In a map of several courses
+/// (see [`crate::roots`]) each node also has the `namespace` of its course.
+pub fn generate_json_content(graph: &CourseGraph, config: &Config) -> Result<String> {
+    let nodes = graph
+        .nodes()
+        .map(|(_, node)| JsonNode {
+            id: &node.id,
+            title: &node.title,
+            phase: &node.phase,
+            color: config.get_phase_color(&node.phase),
+            file_path: node.file_path.to_string_lossy().to_string(),
+            namespace: node.namespace.as_deref(),
+            metadata: node
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.as_str(), value))
+                .collect(),
+        })
+        .collect();
+
+    let edges = graph
+        .typed_edges()
+        .map(|(source, target, kind)| JsonEdge {
+            source: &graph.graph[source].id,
+            target: &graph.graph[target].id,
+            kind,
+        })
+        .collect();
+
+    let json_graph = JsonGraph {
+        version: JSON_SCHEMA_VERSION,
+        nodes,
+        edges,
+    };
+
+    let mut json = serde_json::to_string_pretty(&json_graph)
+        .with_context(|| "Failed to serialize graph to JSON")?;
+    json.push('\n');
+
+    Ok(json)
+}
+
 /// Render graph using Graphviz to SVG or PNG
 pub fn render_with_graphviz(
     graph: &CourseGraph,
@@ -97,57 +238,101 @@ pub fn generate_dot_content(graph: &CourseGraph, config: &Config) -> Result<Stri
     for (_node_index, node) in graph.nodes() {
         let color = config.get_phase_color(&node.phase);
         let label = escape_dot_string(&node.display_name);
+        let link = match linked_url(node, config) {
+            Some(url) => format!(", URL=\"{}\", target=\"_top\"", escape_dot_string(url)),
+            None => String::new(),
+        };
 
         writeln!(
             dot,
-            "    \"{}\" [label=\"{}\", fillcolor=\"{}\"];",
+            "    \"{}\" [label=\"{}\", fillcolor=\"{}\"{}];",
             escape_dot_string(&node.id),
             label,
-            color
+            color,
+            link
         )?;
     }
 
     writeln!(dot)?;
 
-    // Add edges
-    for (source_idx, target_idx) in graph.edges() {
+    // Add edges: recommended ones dashed, corequisites without a direction
+    // (and without pushing one course below the other), and those that lie
+    // on a cycle in red
+    let cycle_edges = highlighted_cycle_edges(graph, config);
+    for (source_idx, target_idx, kind) in graph.typed_edges() {
         let source_node = &graph.graph[source_idx];
         let target_node = &graph.graph[target_idx];
+        let mut styles = match kind {
+            EdgeKind::Required => vec![],
+            EdgeKind::Recommended => vec!["style=dashed"],
+            EdgeKind::Corequisite => vec!["dir=both", "constraint=false"],
+        };
+        if on_cycle(&cycle_edges, source_idx, target_idx, kind) {
+            styles.extend(["color=red", "penwidth=2"]);
+        }
+        let attributes = if styles.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", styles.join(", "))
+        };
 
         writeln!(
             dot,
-            "    \"{}\" -> \"{}\";",
+            "    \"{}\" -> \"{}\"{};",
             escape_dot_string(&source_node.id),
-            escape_dot_string(&target_node.id)
+            escape_dot_string(&target_node.id),
+            attributes
         )?;
     }
 
-    // Add phase-based subgraphs for better layout
-    let mut phases: std::collections::HashMap<String, Vec<String>> =
-        std::collections::HashMap::new();
-    for (_, node) in graph.nodes() {
-        phases
-            .entry(node.phase.clone())
-            .or_default()
-            .push(node.id.clone());
-    }
-
-    if phases.len() > 1 {
+    // Add subgraphs for better layout: one per course when the map shows
+    // several, otherwise one per phase in configuration order
+    let courses = graph.courses();
+    if courses.len() > 1 {
         writeln!(dot)?;
-        writeln!(dot, "    // Phase-based clustering")?;
+        writeln!(dot, "    // Course-based clustering")?;
 
-        for (phase, node_ids) in phases {
-            if node_ids.len() > 1 {
-                writeln!(dot, "    subgraph cluster_{} {{", escape_dot_string(&phase))?;
-                writeln!(dot, "        label=\"{phase} Phase\";")?;
-                writeln!(dot, "        style=dashed;")?;
-                writeln!(dot, "        color=lightgray;")?;
+        for (course, nodes) in courses {
+            let course = escape_dot_string(course.unwrap_or_default());
+            writeln!(dot, "    subgraph \"cluster_course_{course}\" {{")?;
+            writeln!(dot, "        label=\"{course}\";")?;
+            writeln!(dot, "        style=rounded;")?;
+            writeln!(dot, "        color=gray;")?;
 
-                for node_id in node_ids {
-                    writeln!(dot, "        \"{}\";", escape_dot_string(&node_id))?;
-                }
+            for node_index in nodes {
+                writeln!(
+                    dot,
+                    "        \"{}\";",
+                    escape_dot_string(&graph.graph[node_index].id)
+                )?;
+            }
+
+            writeln!(dot, "    }}")?;
+        }
+    } else {
+        let phases = group_by_phase(graph, config);
+        if phases.iter().filter(|(_, nodes)| !nodes.is_empty()).count() > 1 {
+            writeln!(dot)?;
+            writeln!(dot, "    // Phase-based clustering")?;
+
+            for (phase, nodes) in phases {
+                if nodes.len() > 1 {
+                    let phase = escape_dot_string(phase);
+                    writeln!(dot, "    subgraph \"cluster_{phase}\" {{")?;
+                    writeln!(dot, "        label=\"{phase} Phase\";")?;
+                    writeln!(dot, "        style=dashed;")?;
+                    writeln!(dot, "        color=lightgray;")?;
 
-                writeln!(dot, "    }}")?;
+                    for node_index in nodes {
+                        writeln!(
+                            dot,
+                            "        \"{}\";",
+                            escape_dot_string(&graph.graph[node_index].id)
+                        )?;
+                    }
+
+                    writeln!(dot, "    }}")?;
+                }
             }
         }
     }
@@ -157,6 +342,424 @@ pub fn generate_dot_content(graph: &CourseGraph, config: &Config) -> Result<Stri
     Ok(dot)
 }
 
+/// Nodes grouped by phase: one group per configured phase (possibly empty),
+/// in configuration order, then phases missing from the configuration in
+/// order of first appearance
+///
+/// Nodes keep graph order within a group, so the grouping is the same on
+/// every run.
+fn group_by_phase<'a>(
+    graph: &'a CourseGraph,
+    config: &'a Config,
+) -> Vec<(&'a str, Vec<petgraph::graph::NodeIndex>)> {
+    let mut phases: Vec<(&str, Vec<petgraph::graph::NodeIndex>)> = config
+        .phase
+        .keys()
+        .map(|phase| (phase.as_str(), Vec::new()))
+        .collect();
+    for (node_index, node) in graph.nodes() {
+        match phases.iter_mut().find(|(phase, _)| *phase == node.phase) {
+            Some((_, nodes)) => nodes.push(node_index),
+            None => phases.push((&node.phase, vec![node_index])),
+        }
+    }
+    phases
+}
+
+/// Generate Mermaid flowchart content from a course graph
+///
+/// Nodes are grouped into one subgraph per phase (in configuration order,
+/// followed by phases missing from the configuration), or per course in a
+/// map of several courses, and styled with a `classDef` per phase. Node
+/// ids are positional (`n0`, `n1`, ...) because Mermaid reserves words
+/// such as `end` and restricts id characters.
+pub fn generate_mermaid_content(graph: &CourseGraph, config: &Config) -> Result<String> {
+    let mut mermaid = String::new();
+
+    writeln!(mermaid, "flowchart TB")?;
+
+    // Phase styles, including a fallback for phases not in the configuration
+    writeln!(
+        mermaid,
+        "    classDef default fill:{},stroke:#333",
+        config.get_phase_color("")
+    )?;
+    for (phase, phase_config) in &config.phase {
+        writeln!(
+            mermaid,
+            "    classDef {} fill:{},stroke:#333",
+            mermaid_class_name(phase),
+            phase_config.face
+        )?;
+    }
+
+    // One subgraph per course when the map shows several, otherwise one
+    // per phase
+    let courses = graph.courses();
+    let groups: Vec<(String, String, Vec<petgraph::graph::NodeIndex>)> = if courses.len() > 1 {
+        courses
+            .into_iter()
+            .enumerate()
+            .map(|(course_index, (course, nodes))| {
+                (
+                    format!("course{course_index}"),
+                    course.unwrap_or_default().to_string(),
+                    nodes,
+                )
+            })
+            .collect()
+    } else {
+        group_by_phase(graph, config)
+            .into_iter()
+            .enumerate()
+            .map(|(phase_index, (phase, nodes))| {
+                (
+                    format!("phase{phase_index}"),
+                    format!("{phase} Phase"),
+                    nodes,
+                )
+            })
+            .collect()
+    };
+
+    for (group_id, label, nodes) in &groups {
+        if nodes.is_empty() {
+            continue;
+        }
+
+        writeln!(mermaid)?;
+        writeln!(
+            mermaid,
+            "    subgraph {}[\"{}\"]",
+            group_id,
+            escape_mermaid_string(label)
+        )?;
+        for node_index in nodes {
+            let node = &graph.graph[*node_index];
+            let class = if config.phase.contains_key(&node.phase) {
+                format!(":::{}", mermaid_class_name(&node.phase))
+            } else {
+                String::new()
+            };
+            writeln!(
+                mermaid,
+                "        n{}[\"{}\"]{}",
+                node_index.index(),
+                escape_mermaid_string(&node.display_name),
+                class
+            )?;
+        }
+        writeln!(mermaid, "    end")?;
+    }
+
+    let links: Vec<(usize, &str)> = graph
+        .nodes()
+        .filter_map(|(node_index, node)| {
+            linked_url(node, config).map(|url| (node_index.index(), url))
+        })
+        .collect();
+    if !links.is_empty() {
+        writeln!(mermaid)?;
+        for (index, url) in links {
+            writeln!(
+                mermaid,
+                "    click n{} href \"{}\" _top",
+                index,
+                escape_mermaid_string(url)
+            )?;
+        }
+    }
+
+    // Add edges: recommended ones dotted, corequisites with arrows at both
+    // ends, and those that lie on a cycle in red
+    let cycle_edges = highlighted_cycle_edges(graph, config);
+    let mut cycle_links = Vec::new();
+
+    writeln!(mermaid)?;
+    for (link_index, (source_idx, target_idx, kind)) in graph.typed_edges().enumerate() {
+        let arrow = match kind {
+            EdgeKind::Required => "-->",
+            EdgeKind::Recommended => "-.->",
+            EdgeKind::Corequisite => "<-->",
+        };
+        writeln!(
+            mermaid,
+            "    n{} {arrow} n{}",
+            source_idx.index(),
+            target_idx.index()
+        )?;
+        if on_cycle(&cycle_edges, source_idx, target_idx, kind) {
+            cycle_links.push(link_index.to_string());
+        }
+    }
+
+    if !cycle_links.is_empty() {
+        writeln!(
+            mermaid,
+            "    linkStyle {} stroke:red,stroke-width:2px",
+            cycle_links.join(",")
+        )?;
+    }
+
+    Ok(mermaid)
+}
+
+/// Render graph to SVG with the built-in layout engine (no Graphviz needed)
+pub fn render_native_svg(graph: &CourseGraph, output_path: &str, config: &Config) -> Result<()> {
+    let svg_content = generate_svg_content(graph, config)?;
+    fs::write(output_path, svg_content)
+        .with_context(|| format!("Failed to write SVG file: {output_path}"))?;
+
+    Ok(())
+}
+
+/// Generate SVG content from a course graph using the built-in layout engine
+pub fn generate_svg_content(graph: &CourseGraph, config: &Config) -> Result<String> {
+    let layout = layout::compute_layout(graph);
+    let mut svg = String::new();
+
+    writeln!(svg, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}pt\" height=\"{h:.0}pt\" viewBox=\"0 0 {w:.1} {h:.1}\">",
+        w = layout.width,
+        h = layout.height
+    )?;
+    writeln!(svg, "<defs>")?;
+    for (marker_id, color) in [("arrowhead", "gray"), ("arrowhead-cycle", "red")] {
+        writeln!(
+            svg,
+            "  <marker id=\"{marker_id}\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\">"
+        )?;
+        writeln!(
+            svg,
+            "    <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{color}\"/>"
+        )?;
+        writeln!(svg, "  </marker>")?;
+    }
+    writeln!(svg, "</defs>")?;
+    writeln!(
+        svg,
+        "<g class=\"graph\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"14\">"
+    )?;
+
+    let cycle_edges = highlighted_cycle_edges(graph, config);
+
+    // Edges first so that nodes are drawn on top of them
+    for edge in &layout.edges {
+        let path = edge
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| format!("{} {x:.1} {y:.1}", if i == 0 { "M" } else { "L" }))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writeln!(svg, "  <g class=\"edge\">")?;
+        writeln!(
+            svg,
+            "    <title>{} -&gt; {}</title>",
+            escape_xml_string(&graph.graph[edge.source].id),
+            escape_xml_string(&graph.graph[edge.target].id)
+        )?;
+        let (stroke, marker) = if on_cycle(&cycle_edges, edge.source, edge.target, edge.kind) {
+            ("red", "arrowhead-cycle")
+        } else {
+            ("gray", "arrowhead")
+        };
+        let style = match edge.kind {
+            EdgeKind::Required => String::new(),
+            EdgeKind::Recommended => " stroke-dasharray=\"6 4\"".to_string(),
+            EdgeKind::Corequisite => format!(" marker-start=\"url(#{marker})\""),
+        };
+        writeln!(
+            svg,
+            "    <path d=\"{path}\" fill=\"none\" stroke=\"{stroke}\"{style} marker-end=\"url(#{marker})\"/>"
+        )?;
+        writeln!(svg, "  </g>")?;
+    }
+
+    for node_box in &layout.nodes {
+        let node = &graph.graph[node_box.index];
+        let lines: Vec<&str> = node.display_name.lines().collect();
+        let url = linked_url(node, config);
+
+        if let Some(url) = url {
+            writeln!(
+                svg,
+                "  <a href=\"{}\" target=\"_top\">",
+                escape_xml_string(url)
+            )?;
+        }
+        writeln!(
+            svg,
+            "  <g class=\"node\" id=\"node-{}\">",
+            escape_xml_string(&node.id)
+        )?;
+        writeln!(svg, "    <title>{}</title>", escape_xml_string(&node.id))?;
+        writeln!(
+            svg,
+            "    <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" stroke=\"black\"/>",
+            node_box.left(),
+            node_box.top(),
+            node_box.width,
+            node_box.height,
+            escape_xml_string(&config.get_phase_color(&node.phase))
+        )?;
+
+        // Center the block of lines vertically around the node center
+        let first_baseline = node_box.y - (lines.len() as f64 - 1.0) * 9.0 + 5.0;
+        write!(
+            svg,
+            "    <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">",
+            node_box.x, first_baseline
+        )?;
+        for (i, line) in lines.iter().enumerate() {
+            let dy = if i == 0 { 0.0 } else { 18.0 };
+            write!(
+                svg,
+                "<tspan x=\"{:.1}\" dy=\"{dy:.0}\">{}</tspan>",
+                node_box.x,
+                escape_xml_string(line)
+            )?;
+        }
+        writeln!(svg, "</text>")?;
+        writeln!(svg, "  </g>")?;
+        if url.is_some() {
+            writeln!(svg, "  </a>")?;
+        }
+    }
+
+    writeln!(svg, "</g>")?;
+    writeln!(svg, "</svg>")?;
+
+    Ok(svg)
+}
+
+/// Page template for HTML output, with placeholders for the SVG and course data
+const HTML_TEMPLATE: &str = include_str!("course-map.html");
+
+#[derive(Serialize)]
+struct HtmlCourse<'a> {
+    id: &'a str,
+    title: &'a str,
+    phase: &'a str,
+    prerequisites: Vec<&'a str>,
+    recommended: Vec<&'a str>,
+    corequisites: Vec<&'a str>,
+    url: Option<&'a str>,
+}
+
+/// Generate a standalone HTML page embedding the native SVG
+///
+/// The page has no external dependencies: styles, scripts and course data
+/// are inlined so it works offline. It supports pan/zoom, searching by id or
+/// title, tooltips with title, phase and dependencies, and following a node
+/// to its `url` (see [`CourseGraph::set_page_urls`]).
+pub fn generate_html_content(graph: &CourseGraph, config: &Config) -> Result<String> {
+    // The page script handles clicks itself, so leave nodes unwrapped
+    let svg_config = Config {
+        links: None,
+        ..config.clone()
+    };
+    let svg_content = generate_svg_content(graph, &svg_config)?;
+    // Inline SVG must not carry an XML declaration
+    let svg_element = svg_content
+        .split_once('\n')
+        .map_or(svg_content.as_str(), |(_, rest)| rest)
+        .trim_end();
+
+    let mut courses: Vec<HtmlCourse> = graph
+        .nodes()
+        .map(|(_, node)| HtmlCourse {
+            id: &node.id,
+            title: &node.title,
+            phase: &node.phase,
+            prerequisites: Vec::new(),
+            recommended: Vec::new(),
+            corequisites: Vec::new(),
+            url: node.url.as_deref(),
+        })
+        .collect();
+    for (source, target, kind) in graph.typed_edges() {
+        let (source_id, target_id) = (&graph.graph[source].id, &graph.graph[target].id);
+        match kind {
+            EdgeKind::Required => courses[target.index()].prerequisites.push(source_id),
+            EdgeKind::Recommended => courses[target.index()].recommended.push(source_id),
+            // Listed on both courses, whichever one declared it
+            EdgeKind::Corequisite => {
+                courses[target.index()].corequisites.push(source_id);
+                courses[source.index()].corequisites.push(target_id);
+            }
+        }
+    }
+    // Keep "</script>" in titles from closing the data block early
+    let course_data = serde_json::to_string(&courses)
+        .with_context(|| "Failed to serialize course data")?
+        .replace("</", "<\\/");
+
+    Ok(HTML_TEMPLATE
+        .replace("<!--COURSE_SVG-->", svg_element)
+        .replace("/*COURSE_DATA*/", &course_data))
+}
+
+/// Edges to highlight as part of a cycle, if enabled in the configuration
+fn highlighted_cycle_edges(
+    graph: &CourseGraph,
+    config: &Config,
+) -> HashSet<(petgraph::graph::NodeIndex, petgraph::graph::NodeIndex)> {
+    if config.highlight_cycles {
+        graph.cycle_edges()
+    } else {
+        HashSet::new()
+    }
+}
+
+/// Whether an edge is highlighted as part of a cycle
+///
+/// Corequisites never are, even when they join two courses on a cycle.
+fn on_cycle(
+    cycle_edges: &HashSet<(petgraph::graph::NodeIndex, petgraph::graph::NodeIndex)>,
+    source: petgraph::graph::NodeIndex,
+    target: petgraph::graph::NodeIndex,
+    kind: EdgeKind,
+) -> bool {
+    kind != EdgeKind::Corequisite && cycle_edges.contains(&(source, target))
+}
+
+/// A node's page URL, if linking is enabled in the configuration
+fn linked_url<'a>(node: &'a CourseNode, config: &Config) -> Option<&'a str> {
+    config.links.as_ref().and(node.url.as_deref())
+}
+
+/// Escape special characters for XML text and attribute values
+fn escape_xml_string(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Escape a label for use inside a quoted Mermaid node or subgraph label
+fn escape_mermaid_string(s: &str) -> String {
+    s.replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace("\r\n", "<br/>")
+        .replace('\n', "<br/>")
+}
+
+/// Turn a phase name into a valid Mermaid class name
+fn mermaid_class_name(phase: &str) -> String {
+    let name: String = phase
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("phase_{name}")
+}
+
 /// Escape special characters in DOT strings
 fn escape_dot_string(s: &str) -> String {
     s.replace('\\', "\\\\")
@@ -195,6 +798,7 @@ mod tests {
     use super::*;
     use crate::config::Config;
     use crate::graph::{CourseGraph, CourseNode};
+    use std::collections::HashMap;
 
     fn create_test_graph() -> CourseGraph {
         let mut graph = CourseGraph::new();
@@ -205,6 +809,14 @@ mod tests {
             title: "Introduction".to_string(),
             phase: "Pre".to_string(),
             display_name: "Introduction\n(intro)".to_string(),
+            file_path: "intro.qmd".into(),
+            metadata: HashMap::from([(
+                "title".to_string(),
+                serde_yaml::Value::String("Introduction".to_string()),
+            )]),
+            url: None,
+            order: None,
+            namespace: None,
         };
 
         let node2 = CourseNode {
@@ -212,11 +824,16 @@ mod tests {
             title: "Advanced Topics".to_string(),
             phase: "Post".to_string(),
             display_name: "Advanced Topics\n(advanced)".to_string(),
+            file_path: "advanced.qmd".into(),
+            metadata: HashMap::new(),
+            url: None,
+            order: None,
+            namespace: None,
         };
 
         let idx1 = petgraph.add_node(node1);
         let idx2 = petgraph.add_node(node2);
-        petgraph.add_edge(idx1, idx2, ());
+        petgraph.add_edge(idx1, idx2, EdgeKind::Required);
 
         graph.graph = petgraph;
         graph.node_map.insert("intro".to_string(), idx1);
@@ -242,6 +859,305 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_output_is_deterministic() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir = temp_dir.path();
+        // Created in reverse order, with phases out of configuration order
+        let lessons = [
+            ("z", "Post", "[y]"),
+            ("y", "Pre", "[]"),
+            ("x", "InClass", "[y, v]"),
+            ("w", "Self Study", "[x]"),
+            ("v", "Pre", "[]"),
+            ("u", "Post", "[x, w]"),
+            ("t", "InClass", "[v]"),
+            ("s", "Self Study", "[]"),
+        ];
+        for (id, phase, prerequisites) in lessons {
+            std::fs::write(
+                dir.join(format!("{id}.qmd")),
+                format!(
+                    "---\ncourse-map:\n  id: {id}\n  phase: {phase}\n  prerequisites: {prerequisites}\n---\n"
+                ),
+            )?;
+        }
+
+        let config = Config {
+            engine: crate::config::Engine::Native,
+            ..Config::default()
+        };
+        let app = crate::App::new(config);
+        let input = dir.to_str().unwrap();
+
+        let dot = app.generate_dot_string(input)?;
+        for _ in 0..5 {
+            assert_eq!(app.generate_dot_string(input)?, dot);
+        }
+
+        // Nodes in path order, clusters in configuration order
+        let position = |needle: &str| dot.find(needle).unwrap();
+        let node_lines: Vec<usize> = ["s", "t", "u", "v", "w", "x", "y", "z"]
+            .iter()
+            .map(|id| position(&format!("    \"{id}\" [label")))
+            .collect();
+        assert!(node_lines.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(position("\"cluster_Pre\"") < position("\"cluster_InClass\""));
+        assert!(position("\"cluster_InClass\"") < position("\"cluster_Post\""));
+        assert!(position("\"cluster_Post\"") < position("\"cluster_Self Study\""));
+        assert!(dot.contains("label=\"Self Study Phase\";"));
+
+        for format in ["json", "mermaid", "svg", "html"] {
+            let first = dir.join(format!("first.{format}"));
+            let second = dir.join(format!("second.{format}"));
+            app.run(input, first.to_str().unwrap(), format)?;
+            app.run(input, second.to_str().unwrap(), format)?;
+            assert_eq!(std::fs::read(&first)?, std::fs::read(&second)?, "{format}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_course_clusters() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let programme = temp_dir.path();
+        for (course, id, prerequisites) in [
+            ("econ101", "intro", "[]"),
+            ("econ101", "models", "[intro, stats101:regression]"),
+            ("stats101", "intro", "[]"),
+            ("stats101", "regression", "[intro]"),
+        ] {
+            std::fs::create_dir_all(programme.join(course))?;
+            std::fs::write(
+                programme.join(course).join(format!("{id}.qmd")),
+                format!("---\ncourse-map:\n  id: {id}\n  prerequisites: {prerequisites}\n---\n"),
+            )?;
+        }
+        let roots = crate::roots::CourseRoot::from_specs(&[
+            programme.join("econ101").to_string_lossy(),
+            programme.join("stats101").to_string_lossy(),
+        ])?;
+        let app = crate::App::new(Config::default());
+
+        let output = programme.join("map.dot");
+        let diagnostics = app.run_roots(&roots, output.to_str().unwrap(), "dot")?;
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        let dot = std::fs::read_to_string(&output)?;
+
+        assert!(dot.contains("    \"stats101:regression\" -> \"econ101:models\";\n"));
+        assert!(dot.contains("    \"econ101:intro\" -> \"econ101:models\";\n"));
+        assert!(
+            dot.contains("    subgraph \"cluster_course_econ101\" {\n        label=\"econ101\";\n")
+        );
+        assert!(dot.contains("    subgraph \"cluster_course_stats101\" {"));
+        assert!(!dot.contains("Phase-based clustering"));
+
+        let output = programme.join("map.mmd");
+        app.run_roots(&roots, output.to_str().unwrap(), "mermaid")?;
+        let mermaid = std::fs::read_to_string(&output)?;
+        assert!(mermaid.contains("    subgraph course0[\"econ101\"]\n"));
+        assert!(mermaid.contains("    subgraph course1[\"stats101\"]\n"));
+
+        let output = programme.join("map.json");
+        app.run_roots(&roots, output.to_str().unwrap(), "json")?;
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output)?)?;
+        assert_eq!(json["nodes"][3]["id"], "stats101:regression");
+        assert_eq!(json["nodes"][3]["namespace"], "stats101");
+
+        // Links are relative to the directory holding both courses
+        let config = Config {
+            links: Some(Default::default()),
+            ..Config::default()
+        };
+        let output = programme.join("linked.dot");
+        crate::App::new(config).run_roots(&roots, output.to_str().unwrap(), "dot")?;
+        let dot = std::fs::read_to_string(&output)?;
+        assert!(dot.contains("URL=\"stats101/regression.html\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_html_content() -> Result<()> {
+        let mut graph = create_test_graph();
+        graph.set_page_urls(std::path::Path::new(""), &Default::default());
+        let config = Config::default();
+
+        let html_content = generate_html_content(&graph, &config)?;
+
+        assert!(html_content.starts_with("<!DOCTYPE html>"));
+        assert!(!html_content.contains("<?xml"));
+        assert!(!html_content.contains("COURSE_SVG"));
+        assert!(html_content.contains("<g class=\"node\" id=\"node-intro\">"));
+        assert!(html_content.contains(
+            r#"{"id":"advanced","title":"Advanced Topics","phase":"Post","prerequisites":["intro"],"recommended":[],"corequisites":[],"url":"advanced.html"}"#
+        ));
+        assert!(!html_content.contains("<script src="));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_node_links() -> Result<()> {
+        let mut graph = create_test_graph();
+        let links = crate::config::LinksConfig {
+            base_url: "https://example.org/".to_string(),
+            ..Default::default()
+        };
+        graph.set_page_urls(std::path::Path::new(""), &links);
+
+        // Links are only emitted when configured
+        let config = Config::default();
+        assert!(!generate_dot_content(&graph, &config)?.contains("URL="));
+        assert!(!generate_svg_content(&graph, &config)?.contains("<a "));
+
+        let config = Config {
+            links: Some(links),
+            ..Config::default()
+        };
+        assert!(generate_dot_content(&graph, &config)?.contains(
+            "\"intro\" [label=\"Introduction\\n(intro)\", fillcolor=\"lightblue\", URL=\"https://example.org/intro.html\", target=\"_top\"];"
+        ));
+        assert!(generate_svg_content(&graph, &config)?
+            .contains("  <a href=\"https://example.org/intro.html\" target=\"_top\">\n  <g class=\"node\" id=\"node-intro\">"));
+        assert!(generate_mermaid_content(&graph, &config)?
+            .contains("    click n1 href \"https://example.org/advanced.html\" _top\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_mermaid_content() -> Result<()> {
+        let graph = create_test_graph();
+        let config = Config::default();
+
+        let mermaid_content = generate_mermaid_content(&graph, &config)?;
+
+        assert!(mermaid_content.starts_with("flowchart TB\n"));
+        assert!(mermaid_content.contains("    classDef phase_Pre fill:lightblue,stroke:#333"));
+        assert!(mermaid_content.contains("    subgraph phase0[\"Pre Phase\"]"));
+        assert!(mermaid_content.contains("        n0[\"Introduction<br/>(intro)\"]:::phase_Pre"));
+        assert!(
+            mermaid_content.contains("        n1[\"Advanced Topics<br/>(advanced)\"]:::phase_Post")
+        );
+        assert!(mermaid_content.contains("    n0 --> n1\n"));
+        assert!(!mermaid_content.contains("linkStyle"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edge_kinds_rendered() -> Result<()> {
+        let mut graph = create_test_graph();
+        let documents = [
+            ("reading", "Pre", "Further Reading"),
+            ("lab", "Post", "Lab"),
+        ];
+        for (id, phase, title) in documents {
+            graph.add_node(&crate::parser::Document::new(
+                id.to_string(),
+                title.to_string(),
+                format!("{id}.qmd").into(),
+                phase.to_string(),
+                vec![],
+                HashMap::new(),
+            ));
+        }
+        graph.add_edge_of_kind("reading", "advanced", EdgeKind::Recommended)?;
+        graph.add_edge_of_kind("lab", "advanced", EdgeKind::Corequisite)?;
+        let config = Config::default();
+
+        let dot = generate_dot_content(&graph, &config)?;
+        assert!(dot.contains("    \"intro\" -> \"advanced\";\n"));
+        assert!(dot.contains("    \"reading\" -> \"advanced\" [style=dashed];\n"));
+        assert!(dot.contains("    \"lab\" -> \"advanced\" [dir=both, constraint=false];\n"));
+
+        let mermaid = generate_mermaid_content(&graph, &config)?;
+        assert!(mermaid.contains("    n0 --> n1\n"));
+        assert!(mermaid.contains("    n2 -.-> n1\n"));
+        assert!(mermaid.contains("    n3 <--> n1\n"));
+
+        let svg = generate_svg_content(&graph, &config)?;
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+        assert_eq!(svg.matches("marker-start").count(), 1);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&generate_json_content(&graph, &config)?)?;
+        let kinds: Vec<&str> = json["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|edge| edge["kind"].as_str().unwrap())
+            .collect();
+        assert_eq!(kinds, vec!["required", "recommended", "corequisite"]);
+
+        // Corequisites are listed on both courses
+        let html = generate_html_content(&graph, &config)?;
+        assert!(html.contains(r#""id":"advanced","title":"Advanced Topics","phase":"Post","prerequisites":["intro"],"recommended":["reading"],"corequisites":["lab"]"#));
+        assert!(html.contains(r#""id":"lab","title":"Lab","phase":"Post","prerequisites":[],"recommended":[],"corequisites":["advanced"]"#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_mermaid_string() {
+        assert_eq!(
+            escape_mermaid_string("Say \"hi\" <now>\n& later"),
+            "Say #quot;hi#quot; #lt;now#gt;<br/>#amp; later"
+        );
+        assert_eq!(mermaid_class_name("In Class"), "phase_In_Class");
+    }
+
+    #[test]
+    fn test_cycle_edges_highlighted() -> Result<()> {
+        let mut graph = create_test_graph();
+        graph.add_edge("advanced", "intro")?;
+
+        let config = Config::default();
+        let dot_content = generate_dot_content(&graph, &config)?;
+        assert!(dot_content.contains("\"intro\" -> \"advanced\" [color=red, penwidth=2];"));
+        assert!(dot_content.contains("\"advanced\" -> \"intro\" [color=red, penwidth=2];"));
+
+        let mermaid_content = generate_mermaid_content(&graph, &config)?;
+        assert!(mermaid_content.contains("    linkStyle 0,1 stroke:red,stroke-width:2px"));
+
+        let svg_content = generate_svg_content(&graph, &config)?;
+        assert!(svg_content.contains("stroke=\"red\" marker-end=\"url(#arrowhead-cycle)\""));
+
+        let config = Config {
+            highlight_cycles: false,
+            ..Config::default()
+        };
+        let dot_content = generate_dot_content(&graph, &config)?;
+        assert!(!dot_content.contains("color=red"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_json_content() -> Result<()> {
+        let graph = create_test_graph();
+        let config = Config::default();
+
+        let json_content = generate_json_content(&graph, &config)?;
+        let value: serde_json::Value = serde_json::from_str(&json_content)?;
+
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["nodes"][0]["id"], "intro");
+        assert_eq!(value["nodes"][0]["phase"], "Pre");
+        assert_eq!(value["nodes"][0]["color"], "lightblue");
+        assert_eq!(value["nodes"][0]["file_path"], "intro.qmd");
+        assert_eq!(value["nodes"][0]["metadata"]["title"], "Introduction");
+        assert_eq!(value["nodes"][1]["id"], "advanced");
+        assert_eq!(
+            value["edges"],
+            serde_json::json!([{ "source": "intro", "target": "advanced", "kind": "required" }])
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_escape_dot_string() {
         assert_eq!(escape_dot_string("simple"), "simple");
@@ -250,6 +1166,52 @@ mod tests {
         assert_eq!(escape_dot_string("with\\backslash"), "with\\\\backslash");
     }
 
+    #[test]
+    fn test_generate_svg_content() -> Result<()> {
+        let graph = create_test_graph();
+        let config = Config::default();
+
+        let svg_content = generate_svg_content(&graph, &config)?;
+
+        assert!(svg_content.starts_with("<?xml"));
+        assert!(svg_content.contains("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg_content.contains("id=\"node-intro\""));
+        assert!(svg_content.contains("id=\"node-advanced\""));
+        assert!(svg_content.contains("fill=\"lightblue\""));
+        assert!(svg_content.contains("fill=\"orange\""));
+        assert!(svg_content.contains("<title>intro -&gt; advanced</title>"));
+        assert!(svg_content.contains(">Introduction</tspan>"));
+        assert!(svg_content.trim_end().ends_with("</svg>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_graph_with_native_engine() -> Result<()> {
+        let graph = create_test_graph();
+        let config = Config {
+            engine: Engine::Native,
+            ..Config::default()
+        };
+
+        let temp_file = tempfile::NamedTempFile::with_suffix(".svg")?;
+        let temp_path = temp_file.path().to_str().unwrap();
+
+        render_graph(&graph, temp_path, "svg", &config)?;
+        let content = std::fs::read_to_string(temp_path)?;
+        assert!(content.contains("<svg"));
+
+        assert!(render_graph(&graph, temp_path, "png", &config).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_xml_string() {
+        assert_eq!(escape_xml_string("a < b & c"), "a &lt; b &amp; c");
+        assert_eq!(escape_xml_string("\"quoted\""), "&quot;quoted&quot;");
+    }
+
     #[test]
     fn test_render_dot() -> Result<()> {
         let graph = create_test_graph();
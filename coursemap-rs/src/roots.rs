@@ -0,0 +1,255 @@
+//! Several course directories in one map
+//!
+//! A programme made of several courses (`econ101/`, `stats101/`, ...) is read
+//! as one [`CourseRoot`] per directory. Each root has a namespace that
+//! qualifies the ids of its documents (`stats101:regression`), so lessons in
+//! different courses may share an id and may depend on each other with a
+//! qualified reference. Unqualified references point into the same course.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::path::{Component, PathBuf};
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::extractors::Extractors;
+use crate::parser::{self, ParseReport};
+
+/// Separates a course namespace from a lesson id
+pub const NAMESPACE_SEPARATOR: char = ':';
+
+/// A directory of course documents and the namespace of its ids
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CourseRoot {
+    pub dir: PathBuf,
+    /// `None` leaves ids as they are, as when reading a single directory
+    pub namespace: Option<String>,
+}
+
+impl CourseRoot {
+    /// A root whose ids are qualified with `namespace`
+    pub fn new(dir: impl Into<PathBuf>, namespace: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            namespace: Some(namespace.to_string()),
+        }
+    }
+
+    /// A root whose ids are used unqualified
+    pub fn unqualified(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            namespace: None,
+        }
+    }
+
+    /// A root named after its directory (`courses/econ101` is `econ101`)
+    pub fn named_after_dir(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let name = dir
+            .file_name()
+            .map(|name| name.to_os_string())
+            .or_else(|| dir.canonicalize().ok()?.file_name().map(Into::into))
+            .and_then(|name| name.into_string().ok())
+            .with_context(|| {
+                format!(
+                    "Cannot name a course after '{}'; use NAME=DIR",
+                    dir.display()
+                )
+            })?;
+        Ok(Self::new(dir, &name))
+    }
+
+    /// Roots from command-line style specifications
+    ///
+    /// Each specification is `NAME=DIR` or a plain `DIR`. A single plain
+    /// directory is read unqualified, as usual; with several, plain
+    /// directories are named after themselves.
+    pub fn from_specs<S: AsRef<str>>(specs: &[S]) -> Result<Vec<Self>> {
+        if let [spec] = specs {
+            if !spec.as_ref().contains('=') {
+                return Ok(vec![Self::unqualified(spec.as_ref())]);
+            }
+        }
+        specs.iter().map(|spec| spec.as_ref().parse()).collect()
+    }
+}
+
+impl FromStr for CourseRoot {
+    type Err = anyhow::Error;
+
+    /// `NAME=DIR`, or a plain `DIR` named after itself
+    fn from_str(spec: &str) -> Result<Self> {
+        match spec.split_once('=') {
+            Some((name, dir)) => Ok(Self::new(dir, name)),
+            None => Self::named_after_dir(spec),
+        }
+    }
+}
+
+/// `id` qualified with `namespace`, unless it already names a course
+pub fn qualify(namespace: &str, id: &str) -> String {
+    if id.contains(NAMESPACE_SEPARATOR) {
+        id.to_string()
+    } else {
+        format!("{namespace}{NAMESPACE_SEPARATOR}{id}")
+    }
+}
+
+/// Parse every root into one report, in root order
+///
+/// Documents of a namespaced root get qualified ids and references (see
+/// [`crate::parser::Document::set_namespace`]). Fails if a namespace is
+/// empty, contains the separator or is used by more than one root.
+pub fn parse_roots(
+    roots: &[CourseRoot],
+    config: &Config,
+    extractors: &Extractors,
+) -> Result<ParseReport> {
+    let mut namespaces = HashSet::new();
+    for namespace in roots.iter().filter_map(|root| root.namespace.as_deref()) {
+        if namespace.is_empty() || namespace.contains(NAMESPACE_SEPARATOR) {
+            bail!("Invalid course namespace '{namespace}'");
+        }
+        if !namespaces.insert(namespace) {
+            bail!("Course namespace '{namespace}' is used by more than one input directory");
+        }
+    }
+
+    let mut report = ParseReport::default();
+    for root in roots {
+        let root_report =
+            parser::parse_directory_with(&root.dir.to_string_lossy(), config, extractors)?;
+        report
+            .documents
+            .extend(root_report.documents.into_iter().map(|mut doc| {
+                if let Some(namespace) = &root.namespace {
+                    doc.set_namespace(namespace);
+                }
+                doc
+            }));
+        report.failures.extend(root_report.failures);
+    }
+
+    Ok(report)
+}
+
+/// The deepest directory that contains every root
+///
+/// Page links of a multi-course map are relative to it, so
+/// `programme/econ101/intro.qmd` links to `econ101/intro.html`.
+pub fn common_dir(roots: &[CourseRoot]) -> PathBuf {
+    let mut roots = roots.iter();
+    let Some(first) = roots.next() else {
+        return PathBuf::new();
+    };
+    let mut common: Vec<Component> = first.dir.components().collect();
+    for root in roots {
+        let shared = common
+            .iter()
+            .zip(root.dir.components())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        common.truncate(shared);
+    }
+    common.iter().collect::<PathBuf>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_specs() -> Result<()> {
+        assert_eq!(
+            CourseRoot::from_specs(&["courses"])?,
+            vec![CourseRoot::unqualified("courses")]
+        );
+        assert_eq!(
+            CourseRoot::from_specs(&["programme/econ101", "stats=programme/stats101"])?,
+            vec![
+                CourseRoot::new("programme/econ101", "econ101"),
+                CourseRoot::new("programme/stats101", "stats"),
+            ]
+        );
+        assert_eq!(
+            CourseRoot::from_specs(&["econ=."])?,
+            vec![CourseRoot::new(".", "econ")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_qualify() {
+        assert_eq!(qualify("econ101", "intro"), "econ101:intro");
+        assert_eq!(
+            qualify("econ101", "stats101:regression"),
+            "stats101:regression"
+        );
+    }
+
+    #[test]
+    fn test_common_dir() {
+        let roots = [
+            CourseRoot::new("programme/econ101", "econ101"),
+            CourseRoot::new("programme/stats/101", "stats101"),
+        ];
+        assert_eq!(common_dir(&roots), PathBuf::from("programme"));
+        assert_eq!(common_dir(&roots[..1]), PathBuf::from("programme/econ101"));
+    }
+
+    #[test]
+    fn test_parse_roots() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let econ = temp_dir.path().join("econ101");
+        let stats = temp_dir.path().join("stats101");
+        std::fs::create_dir_all(&econ)?;
+        std::fs::create_dir_all(&stats)?;
+        std::fs::write(
+            econ.join("intro.qmd"),
+            "---\ncourse-map:\n  id: intro\n  phase: Pre\n---\n",
+        )?;
+        std::fs::write(
+            econ.join("models.qmd"),
+            "---\ncourse-map:\n  id: models\n  prerequisites: [intro, stats101:regression]\n---\n",
+        )?;
+        std::fs::write(
+            stats.join("intro.qmd"),
+            "---\ncourse-map:\n  id: intro\n---\n",
+        )?;
+        std::fs::write(
+            stats.join("regression.qmd"),
+            "---\ncourse-map:\n  id: regression\n  prerequisites: [intro]\n---\n",
+        )?;
+
+        let roots = vec![
+            CourseRoot::named_after_dir(&econ)?,
+            CourseRoot::named_after_dir(&stats)?,
+        ];
+        let report = parse_roots(&roots, &Config::default(), &Extractors::default())?;
+
+        let ids: Vec<&str> = report.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "econ101:intro",
+                "econ101:models",
+                "stats101:intro",
+                "stats101:regression"
+            ]
+        );
+        assert_eq!(
+            report.documents[1].prerequisites,
+            vec!["econ101:intro", "stats101:regression"]
+        );
+        assert_eq!(report.documents[3].prerequisites, vec!["stats101:intro"]);
+        assert_eq!(report.documents[3].namespace.as_deref(), Some("stats101"));
+
+        let clash = vec![CourseRoot::new(&econ, "x"), CourseRoot::new(&stats, "x")];
+        let error = parse_roots(&clash, &Config::default(), &Extractors::default()).unwrap_err();
+        assert!(error.to_string().contains("used by more than one"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+//! Polling-based change detection for watch mode
+
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::{Config, FileMatcher};
+use crate::parser;
+
+/// Modification stamp of a single file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+/// State of the watched files at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, FileStamp>,
+}
+
+impl Snapshot {
+    /// Record every course document (a file with one of the `extensions`),
+    /// Quarto metadata file and ignore file under the `dirs` plus the `extra`
+    /// files
+    ///
+    /// Each directory is walked like the parser walks it: its matcher and
+    /// any `.gitignore` or `.quartoignore` files leave documents out. The
+    /// `excluded` files (such as the generated map) are never recorded.
+    /// Extra files (such as the configuration file) that do not exist are
+    /// skipped, so creating one later shows up as a change.
+    pub fn take(
+        dirs: &[(PathBuf, FileMatcher)],
+        extensions: &[String],
+        extra: &[PathBuf],
+        excluded: &[PathBuf],
+    ) -> Self {
+        let documents = dirs
+            .iter()
+            .flat_map(|(dir, matcher)| {
+                parser::walker(dir, matcher)
+                    .build()
+                    .filter_map(|e| e.ok())
+                    .filter(move |e| {
+                        let path = e.path();
+                        e.file_type().is_some_and(|t| t.is_file())
+                            && ((parser::is_course_document(path, extensions)
+                                && !matcher.is_excluded(path))
+                                || parser::is_metadata_file(path)
+                                || parser::is_ignore_file(path))
+                    })
+                    .map(|e| e.into_path())
+            })
+            .filter(|path| !is_one_of(path, excluded));
+
+        let files = documents
+            .chain(extra.iter().cloned())
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                let stamp = FileStamp {
+                    modified: metadata.modified().ok(),
+                    len: metadata.len(),
+                };
+                Some((path, stamp))
+            })
+            .collect();
+
+        Self { files }
+    }
+
+    /// Paths added, removed or modified since `earlier`
+    pub fn changes_since(&self, earlier: &Snapshot) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, stamp)| earlier.files.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(
+            earlier
+                .files
+                .keys()
+                .filter(|path| !self.files.contains_key(*path))
+                .cloned(),
+        );
+        changed.sort();
+        changed
+    }
+}
+
+/// `path` with its directory made canonical, so that two spellings of the
+/// same file compare equal even if the file does not exist yet
+fn canonical(path: &Path) -> PathBuf {
+    let parent = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    match (fs::canonicalize(parent), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Whether `path` is one of the `files`, which are canonical
+fn is_one_of(path: &Path, files: &[PathBuf]) -> bool {
+    files
+        .iter()
+        .any(|file| file.file_name() == path.file_name())
+        && files.contains(&canonical(path))
+}
+
+/// Polls directories and reports changes once edits have settled
+pub struct Watcher {
+    dirs: Vec<(PathBuf, FileMatcher)>,
+    extensions: Vec<String>,
+    extra: Vec<PathBuf>,
+    excluded: Vec<PathBuf>,
+    interval: Duration,
+    debounce: Duration,
+    snapshot: Snapshot,
+}
+
+impl Watcher {
+    /// Start watching the course documents `config` selects in `dirs` and
+    /// the `extra` files, but not the `excluded` ones
+    ///
+    /// The files the app writes must be excluded, or each regeneration
+    /// would be reported as a change and trigger the next one.
+    pub fn new(
+        dirs: Vec<PathBuf>,
+        config: &Config,
+        extra: Vec<PathBuf>,
+        excluded: &[PathBuf],
+        interval: Duration,
+        debounce: Duration,
+    ) -> Result<Self> {
+        let dirs = matchers(dirs, config)?;
+        let excluded: Vec<PathBuf> = excluded.iter().map(|path| canonical(path)).collect();
+        let snapshot = Snapshot::take(&dirs, &config.extensions, &extra, &excluded);
+        Ok(Self {
+            dirs,
+            extensions: config.extensions.clone(),
+            extra,
+            excluded,
+            interval,
+            debounce,
+            snapshot,
+        })
+    }
+
+    /// Watch the documents a different configuration selects from now on
+    pub fn set_config(&mut self, config: &Config) -> Result<()> {
+        let dirs = self.dirs.iter().map(|(dir, _)| dir.clone()).collect();
+        self.dirs = matchers(dirs, config)?;
+        self.extensions = config.extensions.clone();
+        self.snapshot = self.take();
+        Ok(())
+    }
+
+    fn take(&self) -> Snapshot {
+        Snapshot::take(&self.dirs, &self.extensions, &self.extra, &self.excluded)
+    }
+
+    /// Block until files change, then wait for a quiet period of `debounce`
+    ///
+    /// A burst of edits (e.g. an editor saving several files, or a save that
+    /// writes a file twice) is reported once. Returns every path that changed
+    /// during the burst.
+    pub fn wait_for_changes(&mut self) -> Vec<PathBuf> {
+        let mut pending = BTreeSet::new();
+        let mut last_change = Instant::now();
+
+        loop {
+            thread::sleep(self.interval);
+
+            let snapshot = self.take();
+            let changed = snapshot.changes_since(&self.snapshot);
+
+            if !changed.is_empty() {
+                pending.extend(changed);
+                self.snapshot = snapshot;
+                last_change = Instant::now();
+            } else if !pending.is_empty() && last_change.elapsed() >= self.debounce {
+                return pending.into_iter().collect();
+            }
+        }
+    }
+}
+
+/// Each directory with the matcher for its `ignore` and `include` patterns
+fn matchers(dirs: Vec<PathBuf>, config: &Config) -> Result<Vec<(PathBuf, FileMatcher)>> {
+    dirs.into_iter()
+        .map(|dir| {
+            let matcher = config.file_matcher(&dir)?;
+            Ok((dir, matcher))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_changes() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let config = dir.join("coursemap.yml");
+        let extensions = Config::default().extensions;
+        let extra = vec![config.clone()];
+        let dirs = matchers(vec![dir.to_path_buf()], &Config::default())?;
+        fs::write(dir.join("intro.qmd"), "---\ntitle: Intro\n---\n")?;
+        fs::write(dir.join("notes.txt"), "not a course")?;
+
+        let before = Snapshot::take(&dirs, &extensions, &extra, &[]);
+        assert!(before.changes_since(&before).is_empty());
+
+        fs::write(dir.join("intro.qmd"), "---\ntitle: Introduction\n---\n")?;
+        fs::write(dir.join("lab.md"), "# Lab\n")?;
+        fs::write(dir.join("notes.txt"), "still not a course")?;
+        fs::write(&config, "root-key: course-map\n")?;
+        fs::write(dir.join("_metadata.yml"), "course-map:\n  phase: Pre\n")?;
+
+        let after = Snapshot::take(&dirs, &extensions, &extra, &[]);
+        assert_eq!(
+            after.changes_since(&before),
+            vec![
+                dir.join("_metadata.yml"),
+                config.clone(),
+                dir.join("intro.qmd"),
+                dir.join("lab.md")
+            ]
+        );
+
+        fs::remove_file(dir.join("lab.md"))?;
+        let removed = Snapshot::take(&dirs, &extensions, &extra, &[]);
+        assert_eq!(removed.changes_since(&after), vec![dir.join("lab.md")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_for_changes_debounces() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path().to_path_buf();
+        let mut watcher = Watcher::new(
+            vec![dir.clone()],
+            &Config::default(),
+            vec![],
+            &[],
+            Duration::from_millis(10),
+            Duration::from_millis(100),
+        )?;
+
+        let writer_dir = dir.clone();
+        let writer = thread::spawn(move || {
+            for (i, name) in ["a.qmd", "b.qmd", "c.qmd"].iter().enumerate() {
+                thread::sleep(Duration::from_millis(20));
+                fs::write(writer_dir.join(name), "x".repeat(i + 1)).unwrap();
+            }
+        });
+
+        let changed = watcher.wait_for_changes();
+        writer.join().unwrap();
+
+        assert_eq!(
+            changed,
+            vec![dir.join("a.qmd"), dir.join("b.qmd"), dir.join("c.qmd")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_skips_output_and_ignored_files() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        fs::create_dir_all(dir.join("_site"))?;
+        fs::create_dir_all(dir.join("drafts"))?;
+        fs::write(dir.join(".gitignore"), "_site/\n")?;
+        fs::write(dir.join("intro.qmd"), "---\ntitle: Intro\n---\n")?;
+
+        let config = Config {
+            ignore: vec!["drafts/".to_string()],
+            ..Config::default()
+        };
+        // The map is written inside the watched directory, spelled differently
+        let output = dir.join(".").join("map.md");
+        let dirs = matchers(vec![dir.to_path_buf()], &config)?;
+        let excluded = vec![canonical(&output)];
+        let before = Snapshot::take(&dirs, &config.extensions, &[], &excluded);
+
+        fs::write(&output, "```mermaid\nflowchart TD\n```\n")?;
+        fs::write(dir.join("_site/intro.md"), "# Intro\n")?;
+        fs::write(dir.join("drafts/lab.qmd"), "---\ntitle: Lab\n---\n")?;
+        let after = Snapshot::take(&dirs, &config.extensions, &[], &excluded);
+        assert!(after.changes_since(&before).is_empty());
+
+        fs::write(dir.join("intro.qmd"), "---\ntitle: Introduction\n---\n")?;
+        let edited = Snapshot::take(&dirs, &config.extensions, &[], &excluded);
+        assert_eq!(edited.changes_since(&after), vec![dir.join("intro.qmd")]);
+
+        Ok(())
+    }
+}